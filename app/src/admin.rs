@@ -0,0 +1,211 @@
+// Program admin and the configuration it controls
+
+use sails_rs::{gstd::msg, prelude::*};
+
+use crate::{state_mut, state_ref, ForumConfig, ModAction, ModLogEntry, PauseState, PausableAction};
+
+// Upper bound for configurable text lengths so one post can't bloat program memory
+const MAX_CONFIG_TEXT_LEN: u32 = 10_000;
+
+#[derive(Encode, Decode, TypeInfo)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub enum AdminEvent {
+    ConfigUpdated { config: ForumConfig },
+    AdminTransferred { previous: ActorId, new: ActorId },
+    Paused,
+    Unpaused,
+    ActionPaused { action: PausableAction },
+    ActionUnpaused { action: PausableAction },
+    ModeratorAdded { account: ActorId },
+    ModeratorRemoved { account: ActorId },
+    ModLogged { entry: ModLogEntry },
+}
+
+#[derive(Clone)]
+pub struct AdminService(());
+
+impl AdminService {
+    pub fn new() -> Self {
+        Self(())
+    }
+
+    fn log(&mut self, action: ModAction) {
+        let entry = state_mut().log_mod_action(msg::source(), action, String::new());
+        self.emit_event(AdminEvent::ModLogged { entry })
+            .expect("Notification failure");
+    }
+}
+
+fn ensure_admin() -> Result<(), String> {
+    if msg::source() != state_ref().admin {
+        return Err("Only the admin can do this".to_string());
+    }
+    Ok(())
+}
+
+fn validate_config(config: &ForumConfig) -> Result<(), String> {
+    for (name, len) in [("max_post_len", config.max_post_len), ("max_comment_len", config.max_comment_len)] {
+        if len == 0 || len > MAX_CONFIG_TEXT_LEN {
+            return Err(format!("{} must be 1-{}", name, MAX_CONFIG_TEXT_LEN));
+        }
+    }
+    let limits = &config.rate_limits;
+    for limit in [limits.post, limits.comment, limits.vote] {
+        if limit.max_actions > 0 && limit.window_ms == 0 {
+            return Err("Rate limit window must be positive".to_string());
+        }
+    }
+    Ok(())
+}
+
+#[sails_rs::service(events = AdminEvent)]
+impl AdminService {
+    // Replace the whole configuration
+    #[export]
+    pub fn update_config(&mut self, config: ForumConfig) -> Result<(), String> {
+        ensure_admin()?;
+        validate_config(&config)?;
+        state_mut().config = config.clone();
+
+        self.emit_event(AdminEvent::ConfigUpdated { config })
+            .expect("Notification failure");
+        self.log(ModAction::ConfigUpdated);
+
+        Ok(())
+    }
+
+    // Hand the admin role to another account
+    #[export]
+    pub fn transfer_admin(&mut self, new_admin: ActorId) -> Result<(), String> {
+        ensure_admin()?;
+        if new_admin.is_zero() {
+            return Err("Zero address".to_string());
+        }
+        let state = state_mut();
+        let previous = core::mem::replace(&mut state.admin, new_admin);
+
+        self.emit_event(AdminEvent::AdminTransferred { previous, new: new_admin })
+            .expect("Notification failure");
+        self.log(ModAction::AdminTransferred { previous, new: new_admin });
+
+        Ok(())
+    }
+
+    // Stop every mutating call, queries keep working
+    #[export]
+    pub fn pause(&mut self) -> Result<(), String> {
+        ensure_admin()?;
+        let state = state_mut();
+        if state.paused {
+            return Err("Already paused".to_string());
+        }
+        state.paused = true;
+
+        self.emit_event(AdminEvent::Paused).expect("Notification failure");
+        self.log(ModAction::Pause { action: None, paused: true });
+
+        Ok(())
+    }
+
+    #[export]
+    pub fn unpause(&mut self) -> Result<(), String> {
+        ensure_admin()?;
+        let state = state_mut();
+        if !state.paused {
+            return Err("Not paused".to_string());
+        }
+        state.paused = false;
+
+        self.emit_event(AdminEvent::Unpaused).expect("Notification failure");
+        self.log(ModAction::Pause { action: None, paused: false });
+
+        Ok(())
+    }
+
+    // Stop a single kind of action
+    #[export]
+    pub fn pause_action(&mut self, action: PausableAction) -> Result<(), String> {
+        ensure_admin()?;
+        if !state_mut().paused_actions.insert(action) {
+            return Err("Action already paused".to_string());
+        }
+
+        self.emit_event(AdminEvent::ActionPaused { action })
+            .expect("Notification failure");
+        self.log(ModAction::Pause { action: Some(action), paused: true });
+
+        Ok(())
+    }
+
+    #[export]
+    pub fn unpause_action(&mut self, action: PausableAction) -> Result<(), String> {
+        ensure_admin()?;
+        if !state_mut().paused_actions.remove(&action) {
+            return Err("Action not paused".to_string());
+        }
+
+        self.emit_event(AdminEvent::ActionUnpaused { action })
+            .expect("Notification failure");
+        self.log(ModAction::Pause { action: Some(action), paused: false });
+
+        Ok(())
+    }
+
+    // Grant global moderator powers
+    #[export]
+    pub fn add_moderator(&mut self, account: ActorId) -> Result<(), String> {
+        ensure_admin()?;
+        if !state_mut().moderators.insert(account) {
+            return Err("Already a moderator".to_string());
+        }
+
+        self.emit_event(AdminEvent::ModeratorAdded { account })
+            .expect("Notification failure");
+        self.log(ModAction::ModeratorChanged { community_id: None, account, added: true });
+
+        Ok(())
+    }
+
+    #[export]
+    pub fn remove_moderator(&mut self, account: ActorId) -> Result<(), String> {
+        ensure_admin()?;
+        if !state_mut().moderators.remove(&account) {
+            return Err("Not a moderator".to_string());
+        }
+
+        self.emit_event(AdminEvent::ModeratorRemoved { account })
+            .expect("Notification failure");
+        self.log(ModAction::ModeratorChanged { community_id: None, account, added: false });
+
+        Ok(())
+    }
+
+    // Query: Get the global moderators
+    #[export]
+    pub fn get_moderators(&self) -> Vec<ActorId> {
+        state_ref().moderators.iter().copied().collect()
+    }
+
+    // Query: Get the global and per-action pause flags
+    #[export]
+    pub fn get_pause_state(&self) -> PauseState {
+        let state = state_ref();
+        PauseState {
+            paused: state.paused,
+            paused_actions: state.paused_actions.iter().copied().collect(),
+        }
+    }
+
+    // Query: Get the current configuration
+    #[export]
+    pub fn get_config(&self) -> ForumConfig {
+        state_ref().config.clone()
+    }
+
+    // Query: Get the admin account
+    #[export]
+    pub fn get_admin(&self) -> ActorId {
+        state_ref().admin
+    }
+}
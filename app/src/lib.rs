@@ -1,25 +1,494 @@
 #![no_std]
 
-use sails_rs::prelude::*;
+extern crate alloc;
+
+use alloc::collections::{BTreeMap, BTreeSet};
+use sails_rs::{collections::HashSet, prelude::*};
 use session_service::*;
 
+mod admin;
+mod moderation;
+mod ranking;
+mod uri;
+mod vibes;
+
+pub use admin::AdminService;
+pub use moderation::ModerationService;
+pub use vibes::VibesService;
+
 session_service::generate_session_system!(ActionsForSession);
 
-const MAX_TEXT_LEN: usize = 500;
-const MAX_COMMENT_LEN: usize = 500;
+const DEFAULT_MAX_POST_LEN: u32 = 500;
+const DEFAULT_MAX_COMMENT_LEN: u32 = 500;
+const MAX_PAGE_SIZE: u32 = 50;
+const MAX_REVISIONS: usize = 10;
+const TOMBSTONE: &str = "[deleted]";
+const REMOVED_TOMBSTONE: &str = "[removed]";
+const MAX_PINNED_POSTS: usize = 3;
+const MAX_MOD_REASON_LEN: usize = 200;
+const MAX_BLOCKED_USERS: usize = 1000;
+const MAX_FOLLOWING: usize = 1000;
+const MIN_SLUG_LEN: usize = 3;
+const MAX_SLUG_LEN: usize = 32;
+const MAX_COMMUNITY_DESCRIPTION_LEN: usize = 500;
+const MAX_COMMUNITY_RULES_LEN: usize = 2000;
+const MIN_USERNAME_LEN: usize = 3;
+const MAX_USERNAME_LEN: usize = 20;
+const MAX_SOCIAL_HANDLE_LEN: usize = 64;
+const MAX_PROFILE_DESCRIPTION_LEN: usize = 300;
+// Hot, rising and controversial feeds only rank this many of the most recent posts
+// in the window, Top and New read every post
+const MAX_RANK_CANDIDATES: usize = 1000;
+const DEFAULT_SESSION_TIP_CAP: u64 = 500;
+const MS_PER_DAY: u64 = 86_400_000;
+const MS_PER_MINUTE: u64 = 60_000;
 
 static mut STATE: Option<ForumState> = None;
 
+// Tips spent through one session, reset whenever the account signs a new session
+#[derive(Clone)]
+struct SessionSpend {
+    key: ActorId,
+    expires: u64,
+    spent: u64,
+}
+
+// Rewards an actor collected during one UTC day
+#[derive(Clone, Default)]
+struct RewardWindow {
+    day: u64,
+    earned: u64,
+    // Actions rewarded so far, indexed by RewardAction
+    counts: [u32; 4],
+}
+
+// Fixed window an actor's rate limit is counted in
+#[derive(Clone)]
+struct RateWindow {
+    started_at: u64,
+    count: u32,
+}
+
 #[derive(Clone, Default)]
 pub struct ForumState {
+    admin: ActorId,
+    // Emergency stop for every mutating call, or only for some actions
+    paused: bool,
+    paused_actions: HashSet<PausableAction>,
+    // Global moderators, the admin always counts as one
+    moderators: HashSet<ActorId>,
+    community_moderators: HashMap<u64, HashSet<ActorId>>,
+    // Pinned post ids per community, None is the global feed
+    pinned_posts: HashMap<Option<u64>, Vec<u64>>,
+    // Open reports per target, and who reported each one while it is open
+    reports: HashMap<Target, Report>,
+    report_reporters: HashMap<Target, BTreeSet<ActorId>>,
+    // Bans and mutes per account, None scope is program-wide
+    bans: HashMap<(ActorId, Option<u64>), Ban>,
+    // Every moderator and admin action, append-only, entry id is its index
+    mod_log: Vec<ModLogEntry>,
     next_id: u64,
     next_comment_id: u64,
-    posts: Vec<Post>,
-    comments: Vec<Comment>,
+    next_community_id: u64,
+    posts: BTreeMap<u64, Post>,
+    comments: BTreeMap<u64, Comment>,
+    // Secondary indexes, each id list is kept in ascending order
+    comments_by_post: HashMap<u64, Vec<u64>>,
+    replies_by_parent: HashMap<u64, Vec<u64>>,
+    posts_by_author: HashMap<ActorId, Vec<u64>>,
+    posts_by_community: HashMap<u64, Vec<u64>>,
+    // (score, id) of live posts per community, None is every post. Lets Top rank
+    // any window without capping the candidates.
+    posts_by_score: HashMap<Option<u64>, BTreeSet<(i64, u64)>>,
+    communities: BTreeMap<u64, Community>,
+    community_by_slug: HashMap<String, u64>,
+    community_members: HashMap<u64, BTreeSet<ActorId>>,
+    // Lowercased username to its owner
+    usernames: HashMap<String, ActorId>,
+    // Previous versions of edited content, oldest first, capped at MAX_REVISIONS
+    post_revisions: HashMap<u64, Vec<Revision>>,
+    comment_revisions: HashMap<u64, Vec<Revision>>,
     profiles: HashMap<ActorId, Profile>,
     vibes_balances: HashMap<ActorId, u64>,
-    upvotes: HashMap<(u64, ActorId), ()>,
-    comment_upvoted: HashMap<(u64, ActorId), ()>,
+    vibes_allowances: HashMap<(ActorId, ActorId), u64>,
+    vibes_total_supply: u64,
+    config: ForumConfig,
+    session_tip_spent: HashMap<ActorId, SessionSpend>,
+    reward_windows: HashMap<ActorId, RewardWindow>,
+    // (target, voter) pairs that already paid upvote rewards, so toggling can't farm them
+    rewarded_votes: HashSet<(Target, ActorId)>,
+    rate_windows: HashMap<(ActorId, RateLimitedAction), RateWindow>,
+    // Each voter's current direction, per post/comment
+    post_votes: HashMap<u64, HashMap<ActorId, VoteDirection>>,
+    comment_votes: HashMap<u64, HashMap<ActorId, VoteDirection>>,
+    // Accounts each actor chose not to see
+    blocked_users: HashMap<ActorId, BTreeSet<ActorId>>,
+    // Follow graph, kept in both directions
+    following: HashMap<ActorId, BTreeSet<ActorId>>,
+    followers: HashMap<ActorId, BTreeSet<ActorId>>,
+}
+
+impl ForumState {
+    fn log_mod_action(&mut self, moderator: ActorId, action: ModAction, reason: String) -> ModLogEntry {
+        let entry = ModLogEntry {
+            id: self.mod_log.len() as u64,
+            moderator,
+            action,
+            reason,
+            created_at: exec::block_timestamp(),
+        };
+        self.mod_log.push(entry.clone());
+        entry
+    }
+
+    fn ensure_active(&self, action: PausableAction) -> Result<(), String> {
+        if self.paused {
+            return Err("Program is paused".to_string());
+        }
+        if self.paused_actions.contains(&action) {
+            return Err(format!("{:?} is paused", action));
+        }
+        Ok(())
+    }
+
+    fn posts_by_ids(&self, ids: &[u64]) -> Vec<Post> {
+        ids.iter().filter_map(|id| self.posts.get(id)).cloned().collect()
+    }
+
+    fn comments_by_ids(&self, ids: &[u64]) -> Vec<Comment> {
+        ids.iter().filter_map(|id| self.comments.get(id)).cloned().collect()
+    }
+
+    fn is_moderator(&self, account: &ActorId) -> bool {
+        *account == self.admin || self.moderators.contains(account)
+    }
+
+    // Global moderators, the community creator and its appointed moderators
+    fn is_community_moderator(&self, community_id: u64, account: &ActorId) -> bool {
+        self.is_moderator(account)
+            || self.communities.get(&community_id).is_some_and(|c| c.creator == *account)
+            || self.community_moderators.get(&community_id).is_some_and(|m| m.contains(account))
+    }
+
+    fn can_moderate(&self, target: Target, account: &ActorId) -> bool {
+        match self.community_of(target) {
+            Some(cid) => self.is_community_moderator(cid, account),
+            None => self.is_moderator(account),
+        }
+    }
+
+    fn community_of(&self, target: Target) -> Option<u64> {
+        let post_id = match target {
+            Target::Post(id) => Some(id),
+            Target::Comment(id) => self.comments.get(&id).map(|c| c.post_id),
+        };
+        post_id.and_then(|id| self.posts.get(&id)).and_then(|p| p.community_id)
+    }
+
+    // Program-wide and community bans stop everything, mutes still allow voting
+    fn ensure_not_banned(&self, account: ActorId, community_id: Option<u64>, action: RateLimitedAction) -> Result<(), String> {
+        let now = exec::block_timestamp();
+        for scope in core::iter::once(None).chain(community_id.map(Some)) {
+            let Some(ban) = self.bans.get(&(account, scope)) else {
+                continue;
+            };
+            if !ban.is_active(now) || (ban.kind == BanKind::Mute && action == RateLimitedAction::Vote) {
+                continue;
+            }
+            let kind = match ban.kind {
+                BanKind::Ban => "banned",
+                BanKind::Mute => "muted",
+            };
+            return Err(match ban.until {
+                Some(until) => format!("Account is {} until {}", kind, until),
+                None => format!("Account is {}", kind),
+            });
+        }
+        Ok(())
+    }
+
+    fn has_blocked(&self, viewer: &ActorId, author: &ActorId) -> bool {
+        self.blocked_users.get(viewer).is_some_and(|b| b.contains(author))
+    }
+
+    // Hidden content is left out of feeds but can still be fetched by id
+    fn is_listed_post(&self, id: &u64) -> bool {
+        self.posts.get(id).is_some_and(|p| !p.hidden)
+    }
+
+    fn is_listed_comment(&self, id: &u64) -> bool {
+        self.comments.get(id).is_some_and(|c| !c.hidden)
+    }
+
+    // Listed, and not written by someone the viewer blocked
+    fn is_visible_post(&self, id: &u64, viewer: Option<ActorId>) -> bool {
+        self.posts.get(id).is_some_and(|p| !p.hidden && !viewer.is_some_and(|v| self.has_blocked(&v, &p.author)))
+    }
+
+    fn is_visible_comment(&self, id: &u64, viewer: Option<ActorId>) -> bool {
+        self.comments.get(id).is_some_and(|c| !c.hidden && !viewer.is_some_and(|v| self.has_blocked(&v, &c.author)))
+    }
+
+    // Replace a post's content with a marker, keeping its id and comment threads
+    fn tombstone_post(&mut self, post_id: u64, marker: &str) {
+        let Some(post) = self.posts.get_mut(&post_id).filter(|p| !p.deleted) else {
+            return;
+        };
+        post.deleted = true;
+        post.text = marker.to_string();
+        post.image_uri = None;
+        let (author, community_id, score) = (post.author, post.community_id, post.score);
+        self.unpin_post(post_id);
+        self.reindex_score(post_id, community_id, Some(score), None);
+        self.post_revisions.remove(&post_id);
+        self.post_votes.remove(&post_id);
+        self.close_reports(Target::Post(post_id));
+
+        if let Some(profile) = self.profiles.get_mut(&author) {
+            profile.total_posts = profile.total_posts.saturating_sub(1);
+        }
+        if let Some(community) = community_id.and_then(|cid| self.communities.get_mut(&cid)) {
+            community.post_count = community.post_count.saturating_sub(1);
+        }
+    }
+
+    // Drop a post from its feed's pins, freeing the slot
+    fn unpin_post(&mut self, post_id: u64) {
+        let Some(post) = self.posts.get_mut(&post_id).filter(|p| p.pinned) else {
+            return;
+        };
+        post.pinned = false;
+        if let Some(pinned) = self.pinned_posts.get_mut(&post.community_id) {
+            pinned.retain(|id| *id != post_id);
+        }
+    }
+
+    // Move a post between positions of the score indexes, None adds or removes it
+    fn reindex_score(&mut self, post_id: u64, community_id: Option<u64>, previous: Option<i64>, current: Option<i64>) {
+        for feed in core::iter::once(None).chain(community_id.map(Some)) {
+            let index = self.posts_by_score.entry(feed).or_default();
+            if let Some(score) = previous {
+                index.remove(&(score, post_id));
+            }
+            if let Some(score) = current {
+                index.insert((score, post_id));
+            }
+        }
+    }
+
+    // Replace a comment's content with a marker, replies keep their parent
+    fn tombstone_comment(&mut self, comment_id: u64, marker: &str) {
+        let Some(comment) = self.comments.get_mut(&comment_id).filter(|c| !c.deleted) else {
+            return;
+        };
+        comment.deleted = true;
+        comment.text = marker.to_string();
+        comment.image_uri = None;
+        let post_id = comment.post_id;
+        let is_top_level = comment.parent_id.is_none();
+        self.comment_revisions.remove(&comment_id);
+        self.comment_votes.remove(&comment_id);
+        self.close_reports(Target::Comment(comment_id));
+
+        // Replies keep counting towards their parent's reply_count so threads render unchanged
+        if is_top_level {
+            if let Some(post) = self.posts.get_mut(&post_id) {
+                post.comment_count = post.comment_count.saturating_sub(1);
+            }
+        }
+    }
+
+    // Drop a target's queue entry together with its reporters, who may report it
+    // again if it offends again. Returns whether there was an open report.
+    fn close_reports(&mut self, target: Target) -> bool {
+        self.report_reporters.remove(&target);
+        self.reports.remove(&target).is_some()
+    }
+
+    fn profile_mut(&mut self, wallet: ActorId) -> &mut Profile {
+        self.profiles.entry(wallet).or_insert_with(|| Profile::new(wallet))
+    }
+
+    // The wallet's rewards on `day`, empty once the day rolled over
+    fn reward_window(&self, wallet: &ActorId, day: u64) -> RewardWindow {
+        match self.reward_windows.get(wallet) {
+            Some(w) if w.day == day => w.clone(),
+            _ => RewardWindow { day, ..Default::default() },
+        }
+    }
+
+    // Count an action in the wallet's window for `day` and return what it pays
+    fn take_reward(&mut self, wallet: ActorId, action: RewardAction, day: u64) -> u64 {
+        let window = self.reward_windows.entry(wallet).or_default();
+        if window.day != day {
+            *window = RewardWindow { day, ..Default::default() };
+        }
+
+        let amount = self.config.rewards.next_amount(action, window);
+        window.counts[action as usize] = window.counts[action as usize].saturating_add(1);
+        window.earned = window.earned.saturating_add(amount);
+        amount
+    }
+
+    fn reward_allowance(&self, wallet: &ActorId, day: u64) -> RewardAllowance {
+        let window = self.reward_window(wallet, day);
+        let rewards = &self.config.rewards;
+        RewardAllowance {
+            daily_cap: rewards.daily_cap,
+            earned_today: window.earned,
+            remaining_today: rewards.daily_cap.saturating_sub(window.earned),
+            next_post: rewards.next_amount(RewardAction::Post, &window),
+            next_comment: rewards.next_amount(RewardAction::Comment, &window),
+            next_upvote_given: rewards.next_amount(RewardAction::UpvoteGiven, &window),
+            next_upvote_received: rewards.next_amount(RewardAction::UpvoteReceived, &window),
+            resets_at: (day + 1) * MS_PER_DAY,
+        }
+    }
+
+    // Credit the reward for an action and return the amount paid
+    fn award_vibes(&mut self, wallet: ActorId, action: RewardAction) -> u64 {
+        let amount = self.take_reward(wallet, action, exec::block_timestamp() / MS_PER_DAY);
+        if amount == 0 {
+            return 0;
+        }
+
+        let profile = self.profile_mut(wallet);
+        profile.total_vibes_earned = profile.total_vibes_earned.saturating_add(amount);
+        let balance = self.vibes_balances.entry(wallet).or_insert(0);
+        *balance = balance.saturating_add(amount);
+        self.vibes_total_supply = self.vibes_total_supply.saturating_add(amount);
+        amount
+    }
+
+    // Move vibes between balances, failing without side effects if the sender is short
+    fn move_vibes(&mut self, from: ActorId, to: ActorId, amount: u64) -> Result<(), String> {
+        let from_balance = self.vibes_balances.get(&from).copied().unwrap_or(0);
+        let remaining = from_balance.checked_sub(amount).ok_or("Insufficient balance")?;
+        let to_balance = self.vibes_balances.get(&to).copied().unwrap_or(0);
+        let credited = to_balance.checked_add(amount).ok_or("Balance overflow")?;
+
+        if remaining == 0 {
+            self.vibes_balances.remove(&from);
+        } else {
+            self.vibes_balances.insert(from, remaining);
+        }
+        self.vibes_balances.insert(to, credited);
+        Ok(())
+    }
+
+    // What a session will have spent on tips after this one, checked against the cap
+    fn session_spend_after(&self, account: ActorId, session: &SessionData, amount: u64) -> Result<SessionSpend, String> {
+        let spent = match self.session_tip_spent.get(&account) {
+            Some(s) if s.key == session.key && s.expires == session.expires => s.spent,
+            _ => 0,
+        };
+        let spent = spent.saturating_add(amount);
+        if spent > self.config.session_tip_cap {
+            return Err(format!("Session tip cap exceeded (max {} per session)", self.config.session_tip_cap));
+        }
+        Ok(SessionSpend { key: session.key, expires: session.expires, spent })
+    }
+
+    // Say when the actor may try again if the action is over its limit. Nothing is
+    // counted here: a failed call must not use up the limit, see count_rate_limited.
+    fn check_rate_limit(&self, actor: ActorId, action: RateLimitedAction) -> Result<(), String> {
+        let limit = self.config.rate_limits.get(action);
+        if limit.max_actions == 0 {
+            return Ok(());
+        }
+
+        let now = exec::block_timestamp();
+        let Some(window) = self.rate_windows.get(&(actor, action)) else {
+            return Ok(());
+        };
+        let window_end = window.started_at.saturating_add(limit.window_ms);
+        if now < window_end && window.count >= limit.max_actions {
+            return Err(format!("Rate limit exceeded, retry after {}", window_end));
+        }
+        Ok(())
+    }
+
+    // Count an action that went through against the actor's limit
+    fn count_rate_limited(&mut self, actor: ActorId, action: RateLimitedAction) {
+        let limit = self.config.rate_limits.get(action);
+        if limit.max_actions == 0 {
+            return;
+        }
+
+        let now = exec::block_timestamp();
+        let window = self
+            .rate_windows
+            .entry((actor, action))
+            .or_insert(RateWindow { started_at: now, count: 0 });
+        if now >= window.started_at.saturating_add(limit.window_ms) {
+            *window = RateWindow { started_at: now, count: 0 };
+        }
+        window.count += 1;
+    }
+
+    fn author_of(&self, target: Target) -> Option<ActorId> {
+        match target {
+            Target::Post(id) => self.posts.get(&id).map(|p| p.author),
+            Target::Comment(id) => self.comments.get(&id).map(|c| c.author),
+        }
+    }
+
+    // The author a new upvote pays, None for self-upvotes and voters already paid for the target
+    fn claim_vote_reward(&mut self, target: Target, voter: ActorId) -> Option<ActorId> {
+        let author = self.author_of(target)?;
+        (author != voter && self.rewarded_votes.insert((target, voter))).then_some(author)
+    }
+
+    fn current_vote(&self, target: Target, voter: &ActorId) -> VoteDirection {
+        let votes = match target {
+            Target::Post(id) => self.post_votes.get(&id),
+            Target::Comment(id) => self.comment_votes.get(&id),
+        };
+        votes.and_then(|v| v.get(voter)).copied().unwrap_or(VoteDirection::Clear)
+    }
+
+    // Replace the voter's previous vote on the target and recompute its tallies
+    fn apply_vote(&mut self, target: Target, voter: ActorId, direction: VoteDirection) -> Result<VoteTally, String> {
+        let mut rescored = None;
+        let (votes, upvotes, downvotes, score) = match target {
+            Target::Post(id) => {
+                let post = self.posts.get_mut(&id).ok_or("Post not found")?;
+                if post.deleted {
+                    return Err("Post was deleted".to_string());
+                }
+                rescored = Some((id, post.community_id, post.score));
+                (self.post_votes.entry(id).or_default(), &mut post.upvotes, &mut post.downvotes, &mut post.score)
+            }
+            Target::Comment(id) => {
+                let comment = self.comments.get_mut(&id).ok_or("Comment not found")?;
+                if comment.deleted {
+                    return Err("Comment was deleted".to_string());
+                }
+                (self.comment_votes.entry(id).or_default(), &mut comment.upvotes, &mut comment.downvotes, &mut comment.score)
+            }
+        };
+
+        match votes.remove(&voter) {
+            Some(VoteDirection::Up) => *upvotes = upvotes.saturating_sub(1),
+            Some(VoteDirection::Down) => *downvotes = downvotes.saturating_sub(1),
+            _ => {}
+        }
+        match direction {
+            VoteDirection::Up => *upvotes = upvotes.saturating_add(1),
+            VoteDirection::Down => *downvotes = downvotes.saturating_add(1),
+            VoteDirection::Clear => {}
+        }
+        if direction != VoteDirection::Clear {
+            votes.insert(voter, direction);
+        }
+        *score = i64::from(*upvotes) - i64::from(*downvotes);
+
+        let tally = VoteTally { upvotes: *upvotes, downvotes: *downvotes, score: *score, direction, vibes_earned: 0 };
+        if let Some((post_id, community_id, previous)) = rescored {
+            self.reindex_score(post_id, community_id, Some(previous), Some(tally.score));
+        }
+        Ok(tally)
+    }
 }
 
 #[derive(Clone, Encode, Decode, TypeInfo)]
@@ -28,11 +497,21 @@ pub struct ForumState {
 pub struct Post {
     pub id: u64,
     pub author: ActorId,
+    pub community_id: Option<u64>,
     pub text: String,
     pub image_uri: Option<String>,
     pub created_at: u64,
+    pub edited_at: Option<u64>,
+    pub deleted: bool,
+    pub hidden: bool,
+    // Locked posts accept no new comments
+    pub locked: bool,
+    pub pinned: bool,
     pub upvotes: u32,
+    pub downvotes: u32,
+    pub score: i64,
     pub comment_count: u32,
+    pub tips_received: u64,
 }
 
 #[derive(Clone, Encode, Decode, TypeInfo)]
@@ -46,8 +525,78 @@ pub struct Comment {
     pub text: String,
     pub image_uri: Option<String>,
     pub created_at: u64,
+    pub edited_at: Option<u64>,
+    pub deleted: bool,
+    pub hidden: bool,
     pub upvotes: u32,
+    pub downvotes: u32,
+    pub score: i64,
     pub reply_count: u32,
+    pub tips_received: u64,
+}
+
+#[derive(Clone, Copy, Debug, Encode, Decode, TypeInfo, PartialEq, Eq, Hash)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+// A post or comment, used by votes, reports and moderation
+pub enum Target {
+    Post(u64),
+    Comment(u64),
+}
+
+#[derive(Clone, Copy, Debug, Encode, Decode, TypeInfo, PartialEq, Eq)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub enum VoteDirection {
+    Up,
+    Down,
+    Clear,
+}
+
+// One wallet's current vote on a target, Clear if none
+#[derive(Clone, Encode, Decode, TypeInfo)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct VoteState {
+    pub target: Target,
+    pub direction: VoteDirection,
+}
+
+#[derive(Clone, Encode, Decode, TypeInfo)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct VoteTally {
+    pub upvotes: u32,
+    pub downvotes: u32,
+    pub score: i64,
+    // The caller's vote after the call, Clear if none
+    pub direction: VoteDirection,
+    // $VIBES paid to the caller for this vote
+    pub vibes_earned: u64,
+}
+
+#[derive(Clone, Encode, Decode, TypeInfo)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct Community {
+    pub id: u64,
+    pub slug: String,
+    pub description: String,
+    pub rules: String,
+    pub creator: ActorId,
+    pub created_at: u64,
+    pub member_count: u32,
+    pub post_count: u32,
+}
+
+#[derive(Clone, Encode, Decode, TypeInfo)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct Revision {
+    pub text: String,
+    pub image_uri: Option<String>,
+    // When this version was written (creation or previous edit)
+    pub written_at: u64,
 }
 
 #[derive(Clone, Encode, Decode, TypeInfo)]
@@ -62,111 +611,82 @@ pub struct Profile {
     pub created_at: u64,
     pub total_posts: u32,
     pub total_vibes_earned: u64,
+    pub total_tips_received: u64,
+    pub follower_count: u32,
+    pub following_count: u32,
 }
 
-#[derive(Encode, Decode, TypeInfo)]
+// How update_profile changes one field
+#[derive(Clone, Debug, Encode, Decode, TypeInfo, PartialEq, Eq)]
 #[codec(crate = sails_rs::scale_codec)]
 #[scale_info(crate = sails_rs::scale_info)]
-pub enum ForumEvent {
-    PostCreated { post_id: u64 },
-    UpvoteToggled { post_id: u64, upvotes: u32, is_upvoted: bool },
-    Error { message: String },
+pub enum FieldPatch {
+    Keep,
+    Set(String),
+    Clear,
 }
 
-#[derive(Clone)]
-pub struct MiniRedditService(());
-
-fn state_mut() -> &'static mut ForumState {
-    unsafe { STATE.as_mut().expect("State not initialized") }
-}
-
-fn state_ref() -> &'static ForumState {
-    unsafe { STATE.as_ref().expect("State not initialized") }
-}
+impl FieldPatch {
+    fn value(&self) -> Option<&str> {
+        match self {
+            FieldPatch::Set(value) => Some(value),
+            _ => None,
+        }
+    }
 
-impl MiniRedditService {
-    pub fn new() -> Self {
-        Self(())
+    fn apply(self, field: &mut Option<String>) {
+        match self {
+            FieldPatch::Keep => {}
+            FieldPatch::Set(value) => *field = Some(value),
+            FieldPatch::Clear => *field = None,
+        }
     }
 }
 
-#[derive(Debug, Clone, Encode, Decode, TypeInfo, PartialEq, Eq)]
+// Why a profile update was rejected, one variant per rule
+#[derive(Clone, Debug, Encode, Decode, TypeInfo, PartialEq, Eq)]
 #[codec(crate = sails_rs::scale_codec)]
 #[scale_info(crate = sails_rs::scale_info)]
-pub enum ActionsForSession {
-    CreatePost,
-    ToggleUpvote,
-    CreateComment,
-    ToggleCommentUpvote,
-    UpdateProfile,
+pub enum ProfileError {
+    Paused,
+    ProfileNotFound,
+    UsernameLength { min: u32, max: u32 },
+    UsernameCharacters,
+    UsernameTaken,
+    SocialHandleLength { min: u32, max: u32 },
+    SocialHandleCharacters,
+    DescriptionLength { max: u32 },
+    AvatarUri(UriError),
 }
 
-fn get_actor(
-    session_map: &HashMap<ActorId, SessionData>,
-    msg_source: &ActorId,
-    session_for_account: &Option<ActorId>,
-    action: ActionsForSession,
-) -> ActorId {
-    match session_for_account {
-        Some(account) => {
-            let session = session_map
-                .get(account)
-                .expect("No valid session for this account");
+#[derive(Clone, Copy, Debug, Encode, Decode, TypeInfo, PartialEq, Eq)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub enum UriError {
+    TooLong { max: u32 },
+    // Only ipfs://, https:// and bare CIDs are accepted
+    UnsupportedScheme,
+    InvalidCid,
+    InvalidHost,
+    InvalidPath,
+}
 
-            assert!(
-                session.expires > exec::block_timestamp(),
-                "Session expired"
-            );
-            assert!(
-                session.allowed_actions.contains(&action),
-                "Action not allowed"
-            );
-            assert_eq!(
-                session.key, *msg_source,
-                "Sender not authorized for session"
-            );
-            *account
+impl core::fmt::Display for UriError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            UriError::TooLong { max } => write!(f, "URI too long (max {} chars)", max),
+            UriError::UnsupportedScheme => f.write_str("URI must be ipfs://, https:// or a CID"),
+            UriError::InvalidCid => f.write_str("Invalid CID"),
+            UriError::InvalidHost => f.write_str("Invalid host"),
+            UriError::InvalidPath => f.write_str("Invalid URI path"),
         }
-        None => *msg_source,
     }
 }
 
-// Helper function to calculate random vibes reward
-fn calculate_vibes_reward() -> u64 {
-    let timestamp = exec::block_timestamp();
-    let seed = timestamp % 1000;
-    (seed % 100) + 1 // Reward between 1-100 vibes
-}
-
-#[sails_rs::service]
-impl MiniRedditService {
-    // Create a new post
-    #[export]
-    pub fn create_post(&mut self, text: String, image_uri: Option<String>, session_for_account: Option<ActorId>) -> Result<(u64, u64), String> {
-        let trimmed = text.trim();
-        
-        if trimmed.is_empty() && image_uri.is_none() {
-            return Err("Post must have text or image".to_string());
-        }
-        
-        if !trimmed.is_empty() && trimmed.len() > MAX_TEXT_LEN {
-            return Err(format!("Post too long (max {} chars)", MAX_TEXT_LEN));
-        }
-
-        let state = state_mut();
-        let post_id = state.next_id;
-        state.next_id = state.next_id.saturating_add(1);
-
-        let msg_src = msg::source();
-        let sessions = Storage::get_session_map();
-        let actor = get_actor(&sessions, &msg_src, &session_for_account, ActionsForSession::CreatePost);
-
-        // Calculate vibes reward
-        let vibes_earned = calculate_vibes_reward();
-        
-        // Update or create profile
-        let profile = state.profiles.entry(actor).or_insert_with(|| Profile {
-            wallet: actor,
+impl Profile {
+    fn new(wallet: ActorId) -> Self {
+        Self {
+            wallet,
             username: None,
             social_handle: None,
             description: None,
@@ -174,196 +694,1640 @@ impl MiniRedditService {
             created_at: exec::block_timestamp(),
             total_posts: 0,
             total_vibes_earned: 0,
-        });
-        profile.total_posts += 1;
-        profile.total_vibes_earned += vibes_earned;
-        
-        // Update vibes balance
-        *state.vibes_balances.entry(actor).or_insert(0) += vibes_earned;
+            total_tips_received: 0,
+            follower_count: 0,
+            following_count: 0,
+        }
+    }
+}
 
-        let post = Post {
-            id: post_id,
-            author: actor,
-            text: trimmed.to_string(),
-            image_uri,
-            created_at: exec::block_timestamp(),
-            upvotes: 0,
-            comment_count: 0,
-        };
+#[derive(Clone, Copy, Debug, Encode, Decode, TypeInfo, PartialEq, Eq, Hash)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub enum PausableAction {
+    Post,
+    Comment,
+    Vote,
+    // Editing and deleting posts or comments
+    EditContent,
+    Profile,
+    Community,
+    Tip,
+    Report,
+    // $VIBES transfers and approvals
+    Transfer,
+}
+
+#[derive(Clone, Encode, Decode, TypeInfo)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct PauseState {
+    pub paused: bool,
+    pub paused_actions: Vec<PausableAction>,
+}
+
+#[derive(Clone, Copy, Debug, Encode, Decode, TypeInfo, PartialEq, Eq)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub enum ReportReason {
+    Spam,
+    Harassment,
+    HateSpeech,
+    Violence,
+    Nsfw,
+    Misinformation,
+    Other,
+}
+
+#[derive(Clone, Encode, Decode, TypeInfo)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct ReasonCount {
+    pub reason: ReportReason,
+    pub count: u32,
+}
+
+// Open reports against one post or comment, one per reporter
+#[derive(Clone, Encode, Decode, TypeInfo)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct Report {
+    pub target: Target,
+    pub count: u32,
+    pub reasons: Vec<ReasonCount>,
+    pub first_reported_at: u64,
+    pub last_reported_at: u64,
+}
+
+#[derive(Clone, Copy, Debug, Encode, Decode, TypeInfo, PartialEq, Eq)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub enum ReportResolution {
+    // Leave the content as is
+    Dismiss,
+    // Keep the content but flag it hidden
+    Hide,
+    // Replace the content with a "[removed]" tombstone
+    Remove,
+}
+
+#[derive(Clone, Copy, Debug, Encode, Decode, TypeInfo, PartialEq, Eq)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub enum BanKind {
+    // No posts, comments or votes
+    Ban,
+    // No posts or comments, voting still allowed
+    Mute,
+}
+
+#[derive(Clone, Debug, Encode, Decode, TypeInfo, PartialEq, Eq)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct Ban {
+    pub account: ActorId,
+    // None bans from the whole program
+    pub community_id: Option<u64>,
+    pub kind: BanKind,
+    // None is permanent
+    pub until: Option<u64>,
+    pub moderator: ActorId,
+    pub reason: String,
+    pub created_at: u64,
+}
+
+impl Ban {
+    fn is_active(&self, now: u64) -> bool {
+        !matches!(self.until, Some(until) if until <= now)
+    }
+}
+
+// What a moderation log entry did, and to what
+#[derive(Clone, Debug, Encode, Decode, TypeInfo, PartialEq, Eq)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub enum ModAction {
+    Hide { target: Target, hidden: bool },
+    Remove { target: Target },
+    DismissReports { target: Target },
+    Lock { post_id: u64, locked: bool },
+    Pin { post_id: u64, pinned: bool },
+    // None pauses or unpauses the whole program
+    Pause { action: Option<PausableAction>, paused: bool },
+    Ban { account: ActorId, community_id: Option<u64>, kind: BanKind, until: Option<u64> },
+    Unban { account: ActorId, community_id: Option<u64> },
+    // None is a global moderator
+    ModeratorChanged { community_id: Option<u64>, account: ActorId, added: bool },
+    ConfigUpdated,
+    AdminTransferred { previous: ActorId, new: ActorId },
+}
+
+#[derive(Clone, Debug, Encode, Decode, TypeInfo, PartialEq, Eq)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct ModLogEntry {
+    pub id: u64,
+    pub moderator: ActorId,
+    pub action: ModAction,
+    pub reason: String,
+    pub created_at: u64,
+}
+
+#[derive(Clone, Encode, Decode, TypeInfo)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct ModLogPage {
+    pub items: Vec<ModLogEntry>,
+    pub next_cursor: Option<u64>,
+}
+
+// Limits and rewards the admin can tune after deployment
+#[derive(Clone, Debug, Encode, Decode, TypeInfo, PartialEq, Eq)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct ForumConfig {
+    pub max_post_len: u32,
+    pub max_comment_len: u32,
+    pub rewards: RewardTable,
+    pub rate_limits: RateLimits,
+    // Most $VIBES one signless session may tip before the account signs a new one
+    pub session_tip_cap: u64,
+}
+
+impl Default for ForumConfig {
+    fn default() -> Self {
+        Self {
+            max_post_len: DEFAULT_MAX_POST_LEN,
+            max_comment_len: DEFAULT_MAX_COMMENT_LEN,
+            rewards: RewardTable::default(),
+            rate_limits: RateLimits::default(),
+            session_tip_cap: DEFAULT_SESSION_TIP_CAP,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Encode, Decode, TypeInfo, PartialEq, Eq, Hash)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub enum RateLimitedAction {
+    Post,
+    Comment,
+    Vote,
+}
+
+// At most `max_actions` per `window_ms` for one actor, 0 disables the limit
+#[derive(Clone, Copy, Debug, Encode, Decode, TypeInfo, PartialEq, Eq)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct RateLimit {
+    pub max_actions: u32,
+    pub window_ms: u64,
+}
+
+#[derive(Clone, Debug, Encode, Decode, TypeInfo, PartialEq, Eq)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct RateLimits {
+    pub post: RateLimit,
+    pub comment: RateLimit,
+    pub vote: RateLimit,
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        Self {
+            post: RateLimit { max_actions: 5, window_ms: 10 * MS_PER_MINUTE },
+            comment: RateLimit { max_actions: 20, window_ms: 10 * MS_PER_MINUTE },
+            vote: RateLimit { max_actions: 60, window_ms: 10 * MS_PER_MINUTE },
+        }
+    }
+}
+
+impl RateLimits {
+    fn get(&self, action: RateLimitedAction) -> RateLimit {
+        match action {
+            RateLimitedAction::Post => self.post,
+            RateLimitedAction::Comment => self.comment,
+            RateLimitedAction::Vote => self.vote,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Encode, Decode, TypeInfo, PartialEq, Eq)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub enum RewardAction {
+    Post,
+    Comment,
+    UpvoteGiven,
+    UpvoteReceived,
+}
+
+// $VIBES paid per action. The first `full_rate_actions` of each action per day pay in
+// full, every one after that pays half the previous, and nobody earns more than
+// `daily_cap` per day.
+#[derive(Clone, Debug, Encode, Decode, TypeInfo, PartialEq, Eq)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct RewardTable {
+    pub post: u64,
+    pub comment: u64,
+    pub upvote_given: u64,
+    pub upvote_received: u64,
+    pub daily_cap: u64,
+    pub full_rate_actions: u32,
+}
+
+impl Default for RewardTable {
+    fn default() -> Self {
+        Self {
+            post: 50,
+            comment: 25,
+            upvote_given: 10,
+            upvote_received: 5,
+            daily_cap: 1000,
+            full_rate_actions: 5,
+        }
+    }
+}
+
+#[derive(Clone, Encode, Decode, TypeInfo)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct RewardAllowance {
+    pub daily_cap: u64,
+    pub earned_today: u64,
+    pub remaining_today: u64,
+    // What the next action of each kind would pay right now
+    pub next_post: u64,
+    pub next_comment: u64,
+    pub next_upvote_given: u64,
+    pub next_upvote_received: u64,
+    pub resets_at: u64,
+}
+
+impl RewardTable {
+    fn amount(&self, action: RewardAction) -> u64 {
+        match action {
+            RewardAction::Post => self.post,
+            RewardAction::Comment => self.comment,
+            RewardAction::UpvoteGiven => self.upvote_given,
+            RewardAction::UpvoteReceived => self.upvote_received,
+        }
+    }
+
+    fn next_amount(&self, action: RewardAction, window: &RewardWindow) -> u64 {
+        let count = window.counts[action as usize];
+        let base = self.amount(action);
+        let diminished = if count < self.full_rate_actions {
+            base
+        } else {
+            base.checked_shr(count - self.full_rate_actions + 1).unwrap_or(0)
+        };
+        diminished.min(self.daily_cap.saturating_sub(window.earned))
+    }
+}
+
+#[derive(Clone, Copy, Encode, Decode, TypeInfo, PartialEq, Eq)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub enum SortOrder {
+    NewestFirst,
+    OldestFirst,
+}
+
+#[derive(Clone, Copy, Encode, Decode, TypeInfo, PartialEq, Eq)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub enum FeedSort {
+    Hot,
+    Top,
+    New,
+    Rising,
+    Controversial,
+}
+
+#[derive(Clone, Copy, Encode, Decode, TypeInfo, PartialEq, Eq)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub enum TimeWindow {
+    Hour,
+    Day,
+    Week,
+    Month,
+    Year,
+    All,
+}
+
+impl TimeWindow {
+    // Earliest created_at (ms) a post may have to be inside the window
+    fn cutoff(self, now: u64) -> u64 {
+        let hour = 3_600_000;
+        let span = match self {
+            TimeWindow::Hour => hour,
+            TimeWindow::Day => 24 * hour,
+            TimeWindow::Week => 7 * 24 * hour,
+            TimeWindow::Month => 30 * 24 * hour,
+            TimeWindow::Year => 365 * 24 * hour,
+            TimeWindow::All => return 0,
+        };
+        now.saturating_sub(span)
+    }
+}
+
+#[derive(Clone, Encode, Decode, TypeInfo)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct RankedPage {
+    pub items: Vec<Post>,
+    pub next_offset: Option<u32>,
+}
+
+#[derive(Clone, Encode, Decode, TypeInfo)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct PostPage {
+    pub items: Vec<Post>,
+    pub next_cursor: Option<u64>,
+}
+
+#[derive(Clone, Encode, Decode, TypeInfo)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct CommentPage {
+    pub items: Vec<Comment>,
+    pub next_cursor: Option<u64>,
+}
+
+#[derive(Clone, Encode, Decode, TypeInfo)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct CommunityPage {
+    pub items: Vec<Community>,
+    pub next_cursor: Option<u64>,
+}
+
+#[derive(Clone, Encode, Decode, TypeInfo)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct MemberPage {
+    pub items: Vec<ActorId>,
+    pub next_cursor: Option<ActorId>,
+}
+
+#[derive(Encode, Decode, TypeInfo)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub enum ForumEvent {
+    PostCreated { post_id: u64, author: ActorId, community_id: Option<u64>, vibes_earned: u64 },
+    CommentCreated { comment_id: u64, post_id: u64, parent_id: Option<u64>, author: ActorId, vibes_earned: u64 },
+    Voted { target: Target, voter: ActorId, direction: VoteDirection, upvotes: u32, downvotes: u32, score: i64 },
+    UpvoteToggled { post_id: u64, voter: ActorId, upvotes: u32, is_upvoted: bool },
+    CommentUpvoteToggled { comment_id: u64, voter: ActorId, upvotes: u32, is_upvoted: bool },
+    PostEdited { post_id: u64, author: ActorId },
+    CommentEdited { comment_id: u64, author: ActorId },
+    PostDeleted { post_id: u64, author: ActorId },
+    CommentDeleted { comment_id: u64, post_id: u64, author: ActorId },
+    CommunityCreated { community_id: u64, slug: String, creator: ActorId },
+    CommunityJoined { community_id: u64, member: ActorId },
+    CommunityLeft { community_id: u64, member: ActorId },
+    UserBlocked { blocker: ActorId, blocked: ActorId },
+    UserUnblocked { blocker: ActorId, blocked: ActorId },
+    Followed { follower: ActorId, followed: ActorId },
+    Unfollowed { follower: ActorId, followed: ActorId },
+    PostTipped { post_id: u64, from: ActorId, to: ActorId, amount: u64 },
+    CommentTipped { comment_id: u64, from: ActorId, to: ActorId, amount: u64 },
+    ProfileUpdated { wallet: ActorId },
+    ProfileDeleted { wallet: ActorId },
+    VibesEarned { wallet: ActorId, amount: u64, action: RewardAction },
+}
+
+#[derive(Clone)]
+pub struct MiniRedditService(());
+
+fn state_mut() -> &'static mut ForumState {
+    unsafe { STATE.as_mut().expect("State not initialized") }
+}
+
+fn state_ref() -> &'static ForumState {
+    unsafe { STATE.as_ref().expect("State not initialized") }
+}
+
+impl MiniRedditService {
+    pub fn new() -> Self {
+        Self(())
+    }
+
+    // Pay the reward for an action and announce it
+    fn reward(&mut self, state: &mut ForumState, wallet: ActorId, action: RewardAction) -> u64 {
+        let amount = state.award_vibes(wallet, action);
+        if amount > 0 {
+            self.emit_event(ForumEvent::VibesEarned { wallet, amount, action })
+                .expect("Notification failure");
+            vibes::notify_transfer(ActorId::zero(), wallet, amount);
+        }
+        amount
+    }
+
+    // Move a tip from the tipper to the author, enforcing the session spending cap
+    fn tip(state: &mut ForumState, tipper: ActorId, author: ActorId, amount: u64, session_for_account: &Option<ActorId>) -> Result<(), String> {
+        if amount == 0 {
+            return Err("Tip must be positive".to_string());
+        }
+        if tipper == author {
+            return Err("Cannot tip yourself".to_string());
+        }
+
+        let session_spend = match session_for_account {
+            Some(account) => {
+                let sessions = Storage::get_session_map();
+                let session = sessions.get(account).expect("No valid session for this account");
+                Some(state.session_spend_after(*account, session, amount)?)
+            }
+            None => None,
+        };
+
+        state.move_vibes(tipper, author, amount)?;
+        vibes::notify_transfer(tipper, author, amount);
+        if let Some(spend) = session_spend {
+            state.session_tip_spent.insert(tipper, spend);
+        }
+        let profile = state.profile_mut(author);
+        profile.total_tips_received = profile.total_tips_received.saturating_add(amount);
+        Ok(())
+    }
+
+    // A new upvote pays the voter and the author, returns the voter's reward
+    fn reward_vote(&mut self, state: &mut ForumState, target: Target, voter: ActorId, previous: VoteDirection, direction: VoteDirection) -> u64 {
+        if direction != VoteDirection::Up || previous == VoteDirection::Up {
+            return 0;
+        }
+        let Some(author) = state.claim_vote_reward(target, voter) else {
+            return 0;
+        };
+        self.reward(state, author, RewardAction::UpvoteReceived);
+        self.reward(state, voter, RewardAction::UpvoteGiven)
+    }
+}
+
+// Sessions store these by index, so new actions are only ever appended
+#[derive(Debug, Clone, Encode, Decode, TypeInfo, PartialEq, Eq)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub enum ActionsForSession {
+    CreatePost,
+    ToggleUpvote,
+    CreateComment,
+    ToggleCommentUpvote,
+    UpdateProfile,
+    EditPost,
+    EditComment,
+    DeletePost,
+    DeleteComment,
+    CreateCommunity,
+    JoinCommunity,
+    LeaveCommunity,
+    Vote,
+    Tip,
+    Report,
+    BlockUser,
+    DeleteProfile,
+    Follow,
+}
+
+fn get_actor(
+    session_map: &HashMap<ActorId, SessionData>,
+    msg_source: &ActorId,
+    session_for_account: &Option<ActorId>,
+    action: ActionsForSession,
+) -> ActorId {
+    match session_for_account {
+        Some(account) => {
+            let session = session_map
+                .get(account)
+                .expect("No valid session for this account");
+
+            assert!(
+                session.expires > exec::block_timestamp(),
+                "Session expired"
+            );
+            assert!(
+                session.allowed_actions.contains(&action),
+                "Action not allowed"
+            );
+            assert_eq!(
+                session.key, *msg_source,
+                "Sender not authorized for session"
+            );
+            *account
+        }
+        None => *msg_source,
+    }
+}
+
+// Validate post/comment content and return the trimmed text
+fn validate_content<'a>(text: &'a str, image_uri: &Option<String>, max_len: u32, kind: &str) -> Result<&'a str, String> {
+    let trimmed = text.trim();
+
+    if trimmed.is_empty() && image_uri.is_none() {
+        return Err(format!("{} must have text or image", kind));
+    }
+
+    if trimmed.len() > max_len as usize {
+        return Err(format!("{} too long (max {} chars)", kind, max_len));
+    }
+
+    if let Some(image_uri) = image_uri {
+        uri::validate(image_uri).map_err(|e| format!("{} image: {}", kind, e))?;
+    }
+
+    Ok(trimmed)
+}
+
+// Slugs are lowercase ascii letters, digits, '-' and '_', so uniqueness is case-insensitive
+fn validate_slug(slug: &str) -> Result<(), String> {
+    if slug.len() < MIN_SLUG_LEN || slug.len() > MAX_SLUG_LEN {
+        return Err(format!("Slug must be {}-{} chars", MIN_SLUG_LEN, MAX_SLUG_LEN));
+    }
+    if !slug.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'_') {
+        return Err("Slug may only contain a-z, 0-9, '-' and '_'".to_string());
+    }
+    Ok(())
+}
+
+// Usernames are ascii letters, digits and '_', unique ignoring case. Returns the registry key.
+fn validate_username(username: &str) -> Result<String, ProfileError> {
+    if username.len() < MIN_USERNAME_LEN || username.len() > MAX_USERNAME_LEN {
+        return Err(ProfileError::UsernameLength { min: MIN_USERNAME_LEN as u32, max: MAX_USERNAME_LEN as u32 });
+    }
+    if !username.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_') {
+        return Err(ProfileError::UsernameCharacters);
+    }
+    Ok(username.to_ascii_lowercase())
+}
+
+// Handles are free-form ("@alice", "x.com/alice") but short and without whitespace
+fn validate_social_handle(handle: &str) -> Result<(), ProfileError> {
+    if handle.is_empty() || handle.len() > MAX_SOCIAL_HANDLE_LEN {
+        return Err(ProfileError::SocialHandleLength { min: 1, max: MAX_SOCIAL_HANDLE_LEN as u32 });
+    }
+    if !handle.bytes().all(|b| b.is_ascii_graphic()) {
+        return Err(ProfileError::SocialHandleCharacters);
+    }
+    Ok(())
+}
+
+fn validate_description(description: &str) -> Result<(), ProfileError> {
+    if description.len() > MAX_PROFILE_DESCRIPTION_LEN {
+        return Err(ProfileError::DescriptionLength { max: MAX_PROFILE_DESCRIPTION_LEN as u32 });
+    }
+    Ok(())
+}
+
+// Keep the replaced version, dropping the oldest once MAX_REVISIONS is reached
+fn push_revision(history: &mut Vec<Revision>, revision: Revision) {
+    if history.len() >= MAX_REVISIONS {
+        history.remove(0);
+    }
+    history.push(revision);
+}
+
+// Page through an account set in key order, the cursor is the first account to return
+fn paginate_accounts(accounts: Option<&BTreeSet<ActorId>>, cursor: Option<ActorId>, limit: u32) -> MemberPage {
+    let Some(accounts) = accounts else {
+        return MemberPage { items: Vec::new(), next_cursor: None };
+    };
+    let limit = limit.clamp(1, MAX_PAGE_SIZE) as usize;
+    let mut items: Vec<ActorId> = match cursor {
+        Some(c) => accounts.range(c..).take(limit + 1).copied().collect(),
+        None => accounts.iter().take(limit + 1).copied().collect(),
+    };
+    let next_cursor = if items.len() > limit { items.pop() } else { None };
+    MemberPage { items, next_cursor }
+}
+
+// Helper to cut one page out of ids already positioned at the cursor. The cursor is
+// inclusive: it is the id of the first item to return, and `next_cursor` is the id the
+// following page starts at.
+fn paginate(ids: impl Iterator<Item = u64>, limit: u32) -> (Vec<u64>, Option<u64>) {
+    let limit = limit.clamp(1, MAX_PAGE_SIZE) as usize;
+    let mut page: Vec<u64> = ids.take(limit + 1).collect();
+    let next_cursor = if page.len() > limit { page.pop() } else { None };
+    (page, next_cursor)
+}
+
+// Walk the keys of an id-keyed map starting at the cursor
+fn map_ids<V>(map: &BTreeMap<u64, V>, cursor: Option<u64>, order: SortOrder) -> Box<dyn Iterator<Item = u64> + '_> {
+    match (order, cursor) {
+        (SortOrder::NewestFirst, Some(c)) => Box::new(map.range(..=c).rev().map(|(id, _)| *id)),
+        (SortOrder::NewestFirst, None) => Box::new(map.keys().rev().copied()),
+        (SortOrder::OldestFirst, Some(c)) => Box::new(map.range(c..).map(|(id, _)| *id)),
+        (SortOrder::OldestFirst, None) => Box::new(map.keys().copied()),
+    }
+}
+
+// Walk an ascending id index starting at the cursor
+fn index_ids(ids: &[u64], cursor: Option<u64>, order: SortOrder) -> Box<dyn Iterator<Item = u64> + '_> {
+    match order {
+        SortOrder::NewestFirst => {
+            let end = cursor.map_or(ids.len(), |c| ids.partition_point(|&id| id <= c));
+            Box::new(ids[..end].iter().rev().copied())
+        }
+        SortOrder::OldestFirst => {
+            let start = cursor.map_or(0, |c| ids.partition_point(|&id| id < c));
+            Box::new(ids[start..].iter().copied())
+        }
+    }
+}
+
+#[sails_rs::service(events = ForumEvent)]
+impl MiniRedditService {
+    // Create a new post
+    #[export]
+    pub fn create_post(&mut self, text: String, image_uri: Option<String>, community_id: Option<u64>, session_for_account: Option<ActorId>) -> Result<(u64, u64), String> {
+        let state = state_mut();
+        state.ensure_active(PausableAction::Post)?;
+        let trimmed = validate_content(&text, &image_uri, state.config.max_post_len, "Post")?;
+        let msg_src = msg::source();
+        let sessions = Storage::get_session_map();
+        let actor = get_actor(&sessions, &msg_src, &session_for_account, ActionsForSession::CreatePost);
+        state.ensure_not_banned(actor, community_id, RateLimitedAction::Post)?;
+        state.check_rate_limit(actor, RateLimitedAction::Post)?;
+
+        // Posting into a community requires membership
+        if let Some(cid) = community_id {
+            let Some(community) = state.communities.get_mut(&cid) else {
+                return Err("Community not found".to_string());
+            };
+            if !state.community_members.get(&cid).is_some_and(|m| m.contains(&actor)) {
+                return Err("Join the community before posting".to_string());
+            }
+            community.post_count += 1;
+            state.posts_by_community.entry(cid).or_default().push(state.next_id);
+        }
+
+        let post_id = state.next_id;
+        state.next_id = state.next_id.saturating_add(1);
+
+        state.profile_mut(actor).total_posts += 1;
+
+        let post = Post {
+            id: post_id,
+            author: actor,
+            community_id,
+            text: trimmed.to_string(),
+            image_uri,
+            created_at: exec::block_timestamp(),
+            edited_at: None,
+            deleted: false,
+            hidden: false,
+            locked: false,
+            pinned: false,
+            upvotes: 0,
+            downvotes: 0,
+            score: 0,
+            comment_count: 0,
+            tips_received: 0,
+        };
+
+        state.posts.insert(post_id, post);
+        state.posts_by_author.entry(actor).or_default().push(post_id);
+        state.reindex_score(post_id, community_id, None, Some(0));
+
+        state.count_rate_limited(actor, RateLimitedAction::Post);
+        let vibes_earned = self.reward(state, actor, RewardAction::Post);
+        self.emit_event(ForumEvent::PostCreated { post_id, author: actor, community_id, vibes_earned })
+            .expect("Notification failure");
+
+        Ok((post_id, vibes_earned))
+    }
+
+    // Create a community, the creator becomes its first member
+    #[export]
+    pub fn create_community(&mut self, slug: String, description: String, rules: String, session_for_account: Option<ActorId>) -> Result<u64, String> {
+        validate_slug(&slug)?;
+        if description.len() > MAX_COMMUNITY_DESCRIPTION_LEN {
+            return Err(format!("Description too long (max {} chars)", MAX_COMMUNITY_DESCRIPTION_LEN));
+        }
+        if rules.len() > MAX_COMMUNITY_RULES_LEN {
+            return Err(format!("Rules too long (max {} chars)", MAX_COMMUNITY_RULES_LEN));
+        }
+
+        let state = state_mut();
+        state.ensure_active(PausableAction::Community)?;
+        let msg_src = msg::source();
+        let sessions = Storage::get_session_map();
+        let actor = get_actor(&sessions, &msg_src, &session_for_account, ActionsForSession::CreateCommunity);
+
+        if state.community_by_slug.contains_key(&slug) {
+            return Err("Slug already taken".to_string());
+        }
+
+        let community_id = state.next_community_id;
+        state.next_community_id = state.next_community_id.saturating_add(1);
+
+        let community = Community {
+            id: community_id,
+            slug: slug.clone(),
+            description,
+            rules,
+            creator: actor,
+            created_at: exec::block_timestamp(),
+            member_count: 1,
+            post_count: 0,
+        };
+
+        state.communities.insert(community_id, community);
+        state.community_by_slug.insert(slug.clone(), community_id);
+        state.community_members.entry(community_id).or_default().insert(actor);
+
+        self.emit_event(ForumEvent::CommunityCreated { community_id, slug, creator: actor })
+            .expect("Notification failure");
+
+        Ok(community_id)
+    }
+
+    // Join a community
+    #[export]
+    pub fn join_community(&mut self, community_id: u64, session_for_account: Option<ActorId>) -> Result<u32, String> {
+        let state = state_mut();
+        state.ensure_active(PausableAction::Community)?;
+        let msg_src = msg::source();
+        let sessions = Storage::get_session_map();
+        let actor = get_actor(&sessions, &msg_src, &session_for_account, ActionsForSession::JoinCommunity);
+
+        let Some(community) = state.communities.get_mut(&community_id) else {
+            return Err("Community not found".to_string());
+        };
+        if !state.community_members.entry(community_id).or_default().insert(actor) {
+            return Err("Already a member".to_string());
+        }
+        community.member_count += 1;
+        let member_count = community.member_count;
+
+        self.emit_event(ForumEvent::CommunityJoined { community_id, member: actor })
+            .expect("Notification failure");
+
+        Ok(member_count)
+    }
+
+    // Leave a community, existing posts stay in it
+    #[export]
+    pub fn leave_community(&mut self, community_id: u64, session_for_account: Option<ActorId>) -> Result<u32, String> {
+        let state = state_mut();
+        state.ensure_active(PausableAction::Community)?;
+        let msg_src = msg::source();
+        let sessions = Storage::get_session_map();
+        let actor = get_actor(&sessions, &msg_src, &session_for_account, ActionsForSession::LeaveCommunity);
+
+        let Some(community) = state.communities.get_mut(&community_id) else {
+            return Err("Community not found".to_string());
+        };
+        if !state.community_members.get_mut(&community_id).is_some_and(|m| m.remove(&actor)) {
+            return Err("Not a member".to_string());
+        }
+        community.member_count = community.member_count.saturating_sub(1);
+        let member_count = community.member_count;
+
+        self.emit_event(ForumEvent::CommunityLeft { community_id, member: actor })
+            .expect("Notification failure");
+
+        Ok(member_count)
+    }
+
+    // Vote on a post or comment: up, down or clear the caller's vote
+    #[export]
+    pub fn vote(&mut self, target: Target, direction: VoteDirection, session_for_account: Option<ActorId>) -> Result<VoteTally, String> {
+        let state = state_mut();
+        state.ensure_active(PausableAction::Vote)?;
+        let msg_src = msg::source();
+        let sessions = Storage::get_session_map();
+        let sender = get_actor(&sessions, &msg_src, &session_for_account, ActionsForSession::Vote);
+        state.ensure_not_banned(sender, state.community_of(target), RateLimitedAction::Vote)?;
+        state.check_rate_limit(sender, RateLimitedAction::Vote)?;
+
+        let previous = state.current_vote(target, &sender);
+        let mut tally = state.apply_vote(target, sender, direction)?;
+        state.count_rate_limited(sender, RateLimitedAction::Vote);
+        tally.vibes_earned = self.reward_vote(state, target, sender, previous, direction);
+
+        self.emit_event(ForumEvent::Voted {
+            target,
+            voter: sender,
+            direction,
+            upvotes: tally.upvotes,
+            downvotes: tally.downvotes,
+            score: tally.score,
+        })
+        .expect("Notification failure");
+
+        Ok(tally)
+    }
+
+    // Toggle upvote on a post
+    #[export]
+    pub fn toggle_upvote(&mut self, post_id: u64, session_for_account: Option<ActorId>) -> Result<(u32, bool, u64), String> {
+        let state = state_mut();
+        state.ensure_active(PausableAction::Vote)?;
+        let msg_src = msg::source();
+        let sessions = Storage::get_session_map();
+        let sender = get_actor(&sessions, &msg_src, &session_for_account, ActionsForSession::ToggleUpvote);
+        let target = Target::Post(post_id);
+        state.ensure_not_banned(sender, state.community_of(target), RateLimitedAction::Vote)?;
+        state.check_rate_limit(sender, RateLimitedAction::Vote)?;
+
+        let previous = state.current_vote(target, &sender);
+        let direction = match previous {
+            VoteDirection::Up => VoteDirection::Clear,
+            _ => VoteDirection::Up,
+        };
+        let tally = state.apply_vote(target, sender, direction)?;
+        state.count_rate_limited(sender, RateLimitedAction::Vote);
+        let vibes_earned = self.reward_vote(state, target, sender, previous, direction);
+        let (upvotes, is_upvoted) = (tally.upvotes, direction == VoteDirection::Up);
+
+        self.emit_event(ForumEvent::UpvoteToggled { post_id, voter: sender, upvotes, is_upvoted })
+            .expect("Notification failure");
+        // A toggle can also clear a downvote, which only Voted carries
+        self.emit_event(ForumEvent::Voted {
+            target,
+            voter: sender,
+            direction,
+            upvotes: tally.upvotes,
+            downvotes: tally.downvotes,
+            score: tally.score,
+        })
+        .expect("Notification failure");
+
+        Ok((upvotes, is_upvoted, vibes_earned))
+    }
+
+    // Create a comment
+    #[export]
+    pub fn create_comment(&mut self, post_id: u64, parent_id: Option<u64>, text: String, image_uri: Option<String>, session_for_account: Option<ActorId>) -> Result<(u64, u64), String> {
+        let state = state_mut();
+        state.ensure_active(PausableAction::Comment)?;
+        let trimmed = validate_content(&text, &image_uri, state.config.max_comment_len, "Comment")?;
+        let msg_src = msg::source();
+        let sessions = Storage::get_session_map();
+        let actor = get_actor(&sessions, &msg_src, &session_for_account, ActionsForSession::CreateComment);
+        state.ensure_not_banned(actor, state.community_of(Target::Post(post_id)), RateLimitedAction::Comment)?;
+        state.check_rate_limit(actor, RateLimitedAction::Comment)?;
+
+        // Verify post exists
+        let Some(post) = state.posts.get_mut(&post_id) else {
+            return Err("Post not found".to_string());
+        };
+        if post.deleted {
+            return Err("Post was deleted".to_string());
+        }
+        if post.locked {
+            return Err("Post is locked".to_string());
+        }
+
+        // If parent_id is Some, verify parent comment exists
+        if let Some(pid) = parent_id {
+            let Some(parent) = state.comments.get_mut(&pid) else {
+                return Err("Parent comment not found".to_string());
+            };
+            if parent.post_id != post_id {
+                return Err("Parent comment belongs to another post".to_string());
+            }
+            if parent.deleted {
+                return Err("Parent comment was deleted".to_string());
+            }
+            parent.reply_count += 1;
+        } else {
+            // Top-level comment, increment post comment count
+            post.comment_count += 1;
+        }
+
+        let comment_id = state.next_comment_id;
+        state.next_comment_id = state.next_comment_id.saturating_add(1);
+
+        let comment = Comment {
+            id: comment_id,
+            post_id,
+            parent_id,
+            author: actor,
+            text: trimmed.to_string(),
+            image_uri,
+            created_at: exec::block_timestamp(),
+            edited_at: None,
+            deleted: false,
+            hidden: false,
+            upvotes: 0,
+            downvotes: 0,
+            score: 0,
+            reply_count: 0,
+            tips_received: 0,
+        };
+
+        state.comments.insert(comment_id, comment);
+        state.comments_by_post.entry(post_id).or_default().push(comment_id);
+        if let Some(pid) = parent_id {
+            state.replies_by_parent.entry(pid).or_default().push(comment_id);
+        }
+
+        state.count_rate_limited(actor, RateLimitedAction::Comment);
+        let vibes_earned = self.reward(state, actor, RewardAction::Comment);
+        self.emit_event(ForumEvent::CommentCreated { comment_id, post_id, parent_id, author: actor, vibes_earned })
+            .expect("Notification failure");
+
+        Ok((comment_id, vibes_earned))
+    }
+
+    // Edit a post, keeping the previous version in its revision history
+    #[export]
+    pub fn edit_post(&mut self, post_id: u64, text: String, image_uri: Option<String>, session_for_account: Option<ActorId>) -> Result<(), String> {
+        let state = state_mut();
+        state.ensure_active(PausableAction::EditContent)?;
+        let trimmed = validate_content(&text, &image_uri, state.config.max_post_len, "Post")?;
+        let msg_src = msg::source();
+        let sessions = Storage::get_session_map();
+        let actor = get_actor(&sessions, &msg_src, &session_for_account, ActionsForSession::EditPost);
+        state.ensure_not_banned(actor, state.community_of(Target::Post(post_id)), RateLimitedAction::Post)?;
+
+        let Some(post) = state.posts.get_mut(&post_id) else {
+            return Err("Post not found".to_string());
+        };
+        if post.author != actor {
+            return Err("Only the author can edit this post".to_string());
+        }
+        if post.deleted {
+            return Err("Post was deleted".to_string());
+        }
+
+        let now = exec::block_timestamp();
+        let previous = Revision {
+            text: core::mem::replace(&mut post.text, trimmed.to_string()),
+            image_uri: core::mem::replace(&mut post.image_uri, image_uri),
+            written_at: post.edited_at.unwrap_or(post.created_at),
+        };
+        post.edited_at = Some(now);
+        push_revision(state.post_revisions.entry(post_id).or_default(), previous);
+
+        self.emit_event(ForumEvent::PostEdited { post_id, author: actor })
+            .expect("Notification failure");
+
+        Ok(())
+    }
+
+    // Edit a comment, keeping the previous version in its revision history
+    #[export]
+    pub fn edit_comment(&mut self, comment_id: u64, text: String, image_uri: Option<String>, session_for_account: Option<ActorId>) -> Result<(), String> {
+        let state = state_mut();
+        state.ensure_active(PausableAction::EditContent)?;
+        let trimmed = validate_content(&text, &image_uri, state.config.max_comment_len, "Comment")?;
+        let msg_src = msg::source();
+        let sessions = Storage::get_session_map();
+        let actor = get_actor(&sessions, &msg_src, &session_for_account, ActionsForSession::EditComment);
+        state.ensure_not_banned(actor, state.community_of(Target::Comment(comment_id)), RateLimitedAction::Comment)?;
+
+        let Some(comment) = state.comments.get_mut(&comment_id) else {
+            return Err("Comment not found".to_string());
+        };
+        if comment.author != actor {
+            return Err("Only the author can edit this comment".to_string());
+        }
+        if comment.deleted {
+            return Err("Comment was deleted".to_string());
+        }
+
+        let now = exec::block_timestamp();
+        let previous = Revision {
+            text: core::mem::replace(&mut comment.text, trimmed.to_string()),
+            image_uri: core::mem::replace(&mut comment.image_uri, image_uri),
+            written_at: comment.edited_at.unwrap_or(comment.created_at),
+        };
+        comment.edited_at = Some(now);
+        push_revision(state.comment_revisions.entry(comment_id).or_default(), previous);
+
+        self.emit_event(ForumEvent::CommentEdited { comment_id, author: actor })
+            .expect("Notification failure");
+
+        Ok(())
+    }
+
+    // Delete a post, leaving a tombstone so its comment threads stay intact
+    #[export]
+    pub fn delete_post(&mut self, post_id: u64, session_for_account: Option<ActorId>) -> Result<(), String> {
+        let state = state_mut();
+        state.ensure_active(PausableAction::EditContent)?;
+        let msg_src = msg::source();
+        let sessions = Storage::get_session_map();
+        let actor = get_actor(&sessions, &msg_src, &session_for_account, ActionsForSession::DeletePost);
+
+        let Some(post) = state.posts.get_mut(&post_id) else {
+            return Err("Post not found".to_string());
+        };
+        if post.author != actor {
+            return Err("Only the author can delete this post".to_string());
+        }
+        if post.deleted {
+            return Err("Post was deleted".to_string());
+        }
+
+        state.tombstone_post(post_id, TOMBSTONE);
+
+        self.emit_event(ForumEvent::PostDeleted { post_id, author: actor })
+            .expect("Notification failure");
+
+        Ok(())
+    }
+
+    // Delete a comment, leaving a tombstone so replies keep their parent
+    #[export]
+    pub fn delete_comment(&mut self, comment_id: u64, session_for_account: Option<ActorId>) -> Result<(), String> {
+        let state = state_mut();
+        state.ensure_active(PausableAction::EditContent)?;
+        let msg_src = msg::source();
+        let sessions = Storage::get_session_map();
+        let actor = get_actor(&sessions, &msg_src, &session_for_account, ActionsForSession::DeleteComment);
+
+        let Some(comment) = state.comments.get_mut(&comment_id) else {
+            return Err("Comment not found".to_string());
+        };
+        if comment.author != actor {
+            return Err("Only the author can delete this comment".to_string());
+        }
+        if comment.deleted {
+            return Err("Comment was deleted".to_string());
+        }
+
+        let post_id = comment.post_id;
+        state.tombstone_comment(comment_id, TOMBSTONE);
+
+        self.emit_event(ForumEvent::CommentDeleted { comment_id, post_id, author: actor })
+            .expect("Notification failure");
+
+        Ok(())
+    }
+
+    // Toggle comment upvote
+    #[export]
+    pub fn toggle_comment_upvote(&mut self, comment_id: u64, session_for_account: Option<ActorId>) -> Result<(u32, bool, u64), String> {
+        let state = state_mut();
+        state.ensure_active(PausableAction::Vote)?;
+        let msg_src = msg::source();
+        let sessions = Storage::get_session_map();
+        let sender = get_actor(&sessions, &msg_src, &session_for_account, ActionsForSession::ToggleCommentUpvote);
+        let target = Target::Comment(comment_id);
+        state.ensure_not_banned(sender, state.community_of(target), RateLimitedAction::Vote)?;
+        state.check_rate_limit(sender, RateLimitedAction::Vote)?;
+
+        let previous = state.current_vote(target, &sender);
+        let direction = match previous {
+            VoteDirection::Up => VoteDirection::Clear,
+            _ => VoteDirection::Up,
+        };
+        let tally = state.apply_vote(target, sender, direction)?;
+        state.count_rate_limited(sender, RateLimitedAction::Vote);
+        let vibes_earned = self.reward_vote(state, target, sender, previous, direction);
+        let (upvotes, is_upvoted) = (tally.upvotes, direction == VoteDirection::Up);
+
+        self.emit_event(ForumEvent::CommentUpvoteToggled { comment_id, voter: sender, upvotes, is_upvoted })
+            .expect("Notification failure");
+        // A toggle can also clear a downvote, which only Voted carries
+        self.emit_event(ForumEvent::Voted {
+            target,
+            voter: sender,
+            direction,
+            upvotes: tally.upvotes,
+            downvotes: tally.downvotes,
+            score: tally.score,
+        })
+        .expect("Notification failure");
+
+        Ok((upvotes, is_upvoted, vibes_earned))
+    }
+
+    // Tip a post's author in $VIBES, returns the post's total tips
+    #[export]
+    pub fn tip_post(&mut self, post_id: u64, amount: u64, session_for_account: Option<ActorId>) -> Result<u64, String> {
+        let state = state_mut();
+        state.ensure_active(PausableAction::Tip)?;
+        let msg_src = msg::source();
+        let sessions = Storage::get_session_map();
+        let tipper = get_actor(&sessions, &msg_src, &session_for_account, ActionsForSession::Tip);
+
+        let Some(post) = state.posts.get(&post_id) else {
+            return Err("Post not found".to_string());
+        };
+        if post.deleted {
+            return Err("Post was deleted".to_string());
+        }
+        let author = post.author;
+
+        Self::tip(state, tipper, author, amount, &session_for_account)?;
+        let post = state.posts.get_mut(&post_id).expect("Post checked above");
+        post.tips_received = post.tips_received.saturating_add(amount);
+        let tips_received = post.tips_received;
+
+        self.emit_event(ForumEvent::PostTipped { post_id, from: tipper, to: author, amount })
+            .expect("Notification failure");
+
+        Ok(tips_received)
+    }
+
+    // Tip a comment's author in $VIBES, returns the comment's total tips
+    #[export]
+    pub fn tip_comment(&mut self, comment_id: u64, amount: u64, session_for_account: Option<ActorId>) -> Result<u64, String> {
+        let state = state_mut();
+        state.ensure_active(PausableAction::Tip)?;
+        let msg_src = msg::source();
+        let sessions = Storage::get_session_map();
+        let tipper = get_actor(&sessions, &msg_src, &session_for_account, ActionsForSession::Tip);
+
+        let Some(comment) = state.comments.get(&comment_id) else {
+            return Err("Comment not found".to_string());
+        };
+        if comment.deleted {
+            return Err("Comment was deleted".to_string());
+        }
+        let author = comment.author;
+
+        Self::tip(state, tipper, author, amount, &session_for_account)?;
+        let comment = state.comments.get_mut(&comment_id).expect("Comment checked above");
+        comment.tips_received = comment.tips_received.saturating_add(amount);
+        let tips_received = comment.tips_received;
+
+        self.emit_event(ForumEvent::CommentTipped { comment_id, from: tipper, to: author, amount })
+            .expect("Notification failure");
+
+        Ok(tips_received)
+    }
+
+    // Update profile
+    #[export]
+    pub fn update_profile(&mut self, username: FieldPatch, social_handle: FieldPatch, description: FieldPatch, avatar_uri: FieldPatch, session_for_account: Option<ActorId>) -> Result<(), ProfileError> {
+        let state = state_mut();
+        state.ensure_active(PausableAction::Profile).map_err(|_| ProfileError::Paused)?;
+        let msg_src = msg::source();
+        let sessions = Storage::get_session_map();
+        let actor = get_actor(&sessions, &msg_src, &session_for_account, ActionsForSession::UpdateProfile);
+
+        let username_key = username.value().map(validate_username).transpose()?;
+        if let Some(s) = social_handle.value() {
+            validate_social_handle(s)?;
+        }
+        if let Some(d) = description.value() {
+            validate_description(d)?;
+        }
+        if let Some(a) = avatar_uri.value() {
+            uri::validate(a).map_err(ProfileError::AvatarUri)?;
+        }
+        if username_key.as_ref().is_some_and(|key| state.usernames.get(key).is_some_and(|owner| *owner != actor)) {
+            return Err(ProfileError::UsernameTaken);
+        }
+
+        // Release the old name when it is replaced or cleared, then claim the new one
+        if username != FieldPatch::Keep {
+            let old = state.profiles.get(&actor).and_then(|p| p.username.as_deref()).map(str::to_ascii_lowercase);
+            if let Some(old) = old {
+                state.usernames.remove(&old);
+            }
+        }
+        if let Some(key) = username_key {
+            state.usernames.insert(key, actor);
+        }
 
-        state.posts.push(post);
+        let profile = state.profile_mut(actor);
+        username.apply(&mut profile.username);
+        social_handle.apply(&mut profile.social_handle);
+        description.apply(&mut profile.description);
+        avatar_uri.apply(&mut profile.avatar_uri);
 
-        Ok((post_id, vibes_earned))
+        self.emit_event(ForumEvent::ProfileUpdated { wallet: actor })
+            .expect("Notification failure");
+
+        Ok(())
     }
 
-    // Toggle upvote on a post
+    // Wipe personal profile data and the blocklist. Posts, comments, stats and
+    // the $VIBES balance stay with the account.
     #[export]
-    pub fn toggle_upvote(&mut self, post_id: u64, session_for_account: Option<ActorId>) -> Result<(u32, bool), String> {
+    pub fn delete_profile(&mut self, session_for_account: Option<ActorId>) -> Result<(), ProfileError> {
         let state = state_mut();
+        state.ensure_active(PausableAction::Profile).map_err(|_| ProfileError::Paused)?;
         let msg_src = msg::source();
         let sessions = Storage::get_session_map();
-        let sender = get_actor(&sessions, &msg_src, &session_for_account, ActionsForSession::ToggleUpvote);
+        let actor = get_actor(&sessions, &msg_src, &session_for_account, ActionsForSession::DeleteProfile);
 
-        let Some(post) = state.posts.iter_mut().find(|p| p.id == post_id) else {
-            return Err("Post not found".to_string());
+        let Some(profile) = state.profiles.get_mut(&actor) else {
+            return Err(ProfileError::ProfileNotFound);
         };
+        if let Some(username) = profile.username.take() {
+            state.usernames.remove(&username.to_ascii_lowercase());
+        }
+        profile.social_handle = None;
+        profile.description = None;
+        profile.avatar_uri = None;
+        state.blocked_users.remove(&actor);
 
-        let key = (post_id, sender);
+        self.emit_event(ForumEvent::ProfileDeleted { wallet: actor })
+            .expect("Notification failure");
 
-        // Check if already upvoted
-        if state.upvotes.remove(&key).is_some() {
-            // Remove upvote
-            if post.upvotes > 0 {
-                post.upvotes -= 1;
-            }
-            Ok((post.upvotes, false))
-        } else {
-            // Add upvote
-            state.upvotes.insert(key, ());
-            post.upvotes = post.upvotes.saturating_add(1);
-            Ok((post.upvotes, true))
-        }
+        Ok(())
     }
 
-    // Create a comment
+    // Stop seeing an account's posts and comments in viewer-aware queries
     #[export]
-    pub fn create_comment(&mut self, post_id: u64, parent_id: Option<u64>, text: String, image_uri: Option<String>, session_for_account: Option<ActorId>) -> Result<u64, String> {
-        let trimmed = text.trim();
-        
-        if trimmed.is_empty() && image_uri.is_none() {
-            return Err("Comment must have text or image".to_string());
-        }
-        
-        if !trimmed.is_empty() && trimmed.len() > MAX_COMMENT_LEN {
-            return Err(format!("Comment too long (max {} chars)", MAX_COMMENT_LEN));
-        }
-
+    pub fn block_user(&mut self, account: ActorId, session_for_account: Option<ActorId>) -> Result<(), String> {
         let state = state_mut();
-        
-        // Verify post exists
-        let Some(post) = state.posts.iter_mut().find(|p| p.id == post_id) else {
-            return Err("Post not found".to_string());
-        };
+        state.ensure_active(PausableAction::Profile)?;
+        let msg_src = msg::source();
+        let sessions = Storage::get_session_map();
+        let actor = get_actor(&sessions, &msg_src, &session_for_account, ActionsForSession::BlockUser);
 
-        // If parent_id is Some, verify parent comment exists
-        if let Some(pid) = parent_id {
-            let Some(parent) = state.comments.iter_mut().find(|c| c.id == pid) else {
-                return Err("Parent comment not found".to_string());
-            };
-            parent.reply_count += 1;
-        } else {
-            // Top-level comment, increment post comment count
-            post.comment_count += 1;
+        if account == actor {
+            return Err("Cannot block yourself".to_string());
+        }
+        let blocked = state.blocked_users.entry(actor).or_default();
+        if blocked.len() >= MAX_BLOCKED_USERS {
+            return Err(format!("At most {} blocked accounts", MAX_BLOCKED_USERS));
+        }
+        if !blocked.insert(account) {
+            return Err("Already blocked".to_string());
         }
 
-        let comment_id = state.next_comment_id;
-        state.next_comment_id = state.next_comment_id.saturating_add(1);
+        self.emit_event(ForumEvent::UserBlocked { blocker: actor, blocked: account })
+            .expect("Notification failure");
+
+        Ok(())
+    }
 
+    #[export]
+    pub fn unblock_user(&mut self, account: ActorId, session_for_account: Option<ActorId>) -> Result<(), String> {
+        let state = state_mut();
+        state.ensure_active(PausableAction::Profile)?;
         let msg_src = msg::source();
         let sessions = Storage::get_session_map();
-        let actor = get_actor(&sessions, &msg_src, &session_for_account, ActionsForSession::CreateComment);
+        let actor = get_actor(&sessions, &msg_src, &session_for_account, ActionsForSession::BlockUser);
 
-        let comment = Comment {
-            id: comment_id,
-            post_id,
-            parent_id,
-            author: actor,
-            text: trimmed.to_string(),
-            image_uri,
-            created_at: exec::block_timestamp(),
-            upvotes: 0,
-            reply_count: 0,
+        let Some(blocked) = state.blocked_users.get_mut(&actor) else {
+            return Err("Not blocked".to_string());
         };
+        if !blocked.remove(&account) {
+            return Err("Not blocked".to_string());
+        }
+        if blocked.is_empty() {
+            state.blocked_users.remove(&actor);
+        }
 
-        state.comments.push(comment);
+        self.emit_event(ForumEvent::UserUnblocked { blocker: actor, blocked: account })
+            .expect("Notification failure");
 
-        Ok(comment_id)
+        Ok(())
     }
 
-    // Toggle comment upvote
+    // Follow an account to see its posts in the home feed
     #[export]
-    pub fn toggle_comment_upvote(&mut self, comment_id: u64, session_for_account: Option<ActorId>) -> Result<(u32, bool), String> {
+    pub fn follow(&mut self, account: ActorId, session_for_account: Option<ActorId>) -> Result<(), String> {
         let state = state_mut();
+        state.ensure_active(PausableAction::Profile)?;
         let msg_src = msg::source();
         let sessions = Storage::get_session_map();
-        let sender = get_actor(&sessions, &msg_src, &session_for_account, ActionsForSession::ToggleCommentUpvote);
+        let actor = get_actor(&sessions, &msg_src, &session_for_account, ActionsForSession::Follow);
 
-        let Some(comment) = state.comments.iter_mut().find(|c| c.id == comment_id) else {
-            return Err("Comment not found".to_string());
-        };
+        if account == actor {
+            return Err("Cannot follow yourself".to_string());
+        }
+        // Following must not create profiles for wallets that never used the app
+        if !state.profiles.contains_key(&account) {
+            return Err("Account has no profile".to_string());
+        }
+        let following = state.following.entry(actor).or_default();
+        if following.len() >= MAX_FOLLOWING {
+            return Err(format!("At most {} followed accounts", MAX_FOLLOWING));
+        }
+        if !following.insert(account) {
+            return Err("Already following".to_string());
+        }
+        state.followers.entry(account).or_default().insert(actor);
+        state.profile_mut(actor).following_count += 1;
+        if let Some(profile) = state.profiles.get_mut(&account) {
+            profile.follower_count += 1;
+        }
 
-        let key = (comment_id, sender);
+        self.emit_event(ForumEvent::Followed { follower: actor, followed: account })
+            .expect("Notification failure");
 
-        if state.comment_upvoted.remove(&key).is_some() {
-            if comment.upvotes > 0 {
-                comment.upvotes -= 1;
-            }
-            Ok((comment.upvotes, false))
-        } else {
-            state.comment_upvoted.insert(key, ());
-            comment.upvotes = comment.upvotes.saturating_add(1);
-            Ok((comment.upvotes, true))
-        }
+        Ok(())
     }
 
-    // Update profile
     #[export]
-    pub fn update_profile(&mut self, username: Option<String>, social_handle: Option<String>, description: Option<String>, avatar_uri: Option<String>, session_for_account: Option<ActorId>) -> Result<(), String> {
+    pub fn unfollow(&mut self, account: ActorId, session_for_account: Option<ActorId>) -> Result<(), String> {
         let state = state_mut();
+        state.ensure_active(PausableAction::Profile)?;
         let msg_src = msg::source();
         let sessions = Storage::get_session_map();
-        let actor = get_actor(&sessions, &msg_src, &session_for_account, ActionsForSession::UpdateProfile);
-
-        let profile = state.profiles.entry(actor).or_insert_with(|| Profile {
-            wallet: actor,
-            username: None,
-            social_handle: None,
-            description: None,
-            avatar_uri: None,
-            created_at: exec::block_timestamp(),
-            total_posts: 0,
-            total_vibes_earned: 0,
-        });
+        let actor = get_actor(&sessions, &msg_src, &session_for_account, ActionsForSession::Follow);
 
-        if let Some(u) = username {
-            profile.username = Some(u);
+        let Some(following) = state.following.get_mut(&actor) else {
+            return Err("Not following".to_string());
+        };
+        if !following.remove(&account) {
+            return Err("Not following".to_string());
         }
-        if let Some(s) = social_handle {
-            profile.social_handle = Some(s);
+        if following.is_empty() {
+            state.following.remove(&actor);
         }
-        if let Some(d) = description {
-            profile.description = Some(d);
+        if let Some(followers) = state.followers.get_mut(&account) {
+            followers.remove(&actor);
+            if followers.is_empty() {
+                state.followers.remove(&account);
+            }
         }
-        if let Some(a) = avatar_uri {
-            profile.avatar_uri = Some(a);
+        let profile = state.profile_mut(actor);
+        profile.following_count = profile.following_count.saturating_sub(1);
+        if let Some(profile) = state.profiles.get_mut(&account) {
+            profile.follower_count = profile.follower_count.saturating_sub(1);
         }
 
+        self.emit_event(ForumEvent::Unfollowed { follower: actor, followed: account })
+            .expect("Notification failure");
+
         Ok(())
     }
 
-    // Query: Get all posts
+    // Query: Get the accounts a viewer has blocked
+    #[export]
+    pub fn get_blocked_users(&self, account: ActorId) -> Vec<ActorId> {
+        let state = state_ref();
+        state.blocked_users.get(&account).map(|b| b.iter().copied().collect()).unwrap_or_default()
+    }
+
+    // Query: Get all posts (unbounded, prefer get_posts)
     #[export]
     pub fn get_all_posts(&self) -> Vec<Post> {
         let state = state_ref();
-        state.posts.iter().rev().cloned().collect()
+        state.posts.values().rev().filter(|p| !p.hidden).cloned().collect()
+    }
+
+    // Query: Get one post by id, including hidden ones
+    #[export]
+    pub fn get_post(&self, post_id: u64) -> Option<Post> {
+        let state = state_ref();
+        state.posts.get(&post_id).cloned()
+    }
+
+    // Query: Get one comment by id, including hidden ones
+    #[export]
+    pub fn get_comment(&self, comment_id: u64) -> Option<Comment> {
+        let state = state_ref();
+        state.comments.get(&comment_id).cloned()
+    }
+
+    // Query: Get pinned posts of a community, or of the global feed for None
+    #[export]
+    pub fn get_pinned_posts(&self, community_id: Option<u64>) -> Vec<Post> {
+        let state = state_ref();
+        let ids = state.pinned_posts.get(&community_id).map(Vec::as_slice).unwrap_or_default();
+        let ids: Vec<u64> = ids.iter().copied().filter(|id| state.is_listed_post(id)).collect();
+        state.posts_by_ids(&ids)
+    }
+
+    // Query: Get a page of posts, at most MAX_PAGE_SIZE per call
+    #[export]
+    pub fn get_posts(&self, cursor: Option<u64>, limit: u32, order: SortOrder, viewer: Option<ActorId>) -> PostPage {
+        let state = state_ref();
+        let ids = map_ids(&state.posts, cursor, order).filter(|id| state.is_visible_post(id, viewer));
+        let (ids, next_cursor) = paginate(ids, limit);
+        PostPage { items: state.posts_by_ids(&ids), next_cursor }
+    }
+
+    // Query: Get a ranked page of posts (hot, top, new, rising, controversial), optionally
+    // within one community. Pinned posts come first, ties are broken by newest first.
+    #[export]
+    pub fn get_ranked_posts(&self, sort: FeedSort, window: TimeWindow, community_id: Option<u64>, offset: u32, limit: u32, viewer: Option<ActorId>) -> RankedPage {
+        let state = state_ref();
+        let now = exec::block_timestamp();
+        let cutoff = window.cutoff(now);
+        let visible = move |p: &Post| !p.deleted && !p.hidden && !viewer.is_some_and(|v| state.has_blocked(&v, &p.author));
+
+        // Only pins of this feed lead it, posts pinned elsewhere rank normally
+        let pinned_ids = state.pinned_posts.get(&community_id).map(Vec::as_slice).unwrap_or_default();
+
+        // Posts are created in id order, so the window ends at the first post older than it
+        let newest_first: Box<dyn Iterator<Item = &Post> + '_> = match community_id {
+            Some(cid) => {
+                let ids = state.posts_by_community.get(&cid).map(Vec::as_slice).unwrap_or_default();
+                Box::new(ids.iter().rev().filter_map(|id| state.posts.get(id)))
+            }
+            None => Box::new(state.posts.values().rev()),
+        };
+        let in_window = newest_first
+            .take_while(|p| p.created_at >= cutoff)
+            .filter(|p| visible(p) && !pinned_ids.contains(&p.id));
+
+        let ranked: Box<dyn Iterator<Item = &Post> + '_> = match sort {
+            FeedSort::New => Box::new(in_window),
+            // Old posts keep gaining votes, so Top walks the score index instead of recent posts
+            FeedSort::Top => {
+                let by_score = state.posts_by_score.get(&community_id).into_iter().flat_map(|index| index.iter().rev());
+                Box::new(
+                    by_score
+                        .filter_map(|(_, id)| state.posts.get(id))
+                        .filter(|p| p.created_at >= cutoff && visible(p) && !pinned_ids.contains(&p.id)),
+                )
+            }
+            _ => {
+                let mut ranked: Vec<(i64, &Post)> = in_window
+                    .take(MAX_RANK_CANDIDATES)
+                    .map(|p| (ranking::rank(sort, p, now), p))
+                    .collect();
+                ranked.sort_by(|(a_rank, a), (b_rank, b)| b_rank.cmp(a_rank).then(b.id.cmp(&a.id)));
+                Box::new(ranked.into_iter().map(|(_, p)| p))
+            }
+        };
+
+        let mut feed = pinned_ids
+            .iter()
+            .filter_map(|id| state.posts.get(id))
+            .filter(|p| visible(p))
+            .chain(ranked)
+            .skip(offset as usize);
+
+        let limit = limit.clamp(1, MAX_PAGE_SIZE) as usize;
+        let items: Vec<Post> = feed.by_ref().take(limit).cloned().collect();
+        let next_offset = feed.next().is_some().then(|| offset + items.len() as u32);
+
+        RankedPage { items, next_offset }
+    }
+
+    // Query: Get a page of posts written by one author
+    #[export]
+    pub fn get_posts_by_author(&self, author: ActorId, cursor: Option<u64>, limit: u32, order: SortOrder) -> PostPage {
+        let state = state_ref();
+        let ids = state.posts_by_author.get(&author).map(Vec::as_slice).unwrap_or_default();
+        let ids = index_ids(ids, cursor, order).filter(|id| state.is_listed_post(id));
+        let (ids, next_cursor) = paginate(ids, limit);
+        PostPage { items: state.posts_by_ids(&ids), next_cursor }
     }
 
     // Query: Get comments for a post
     #[export]
     pub fn get_comments_for_post(&self, post_id: u64) -> Vec<Comment> {
         let state = state_ref();
-        state.comments
-            .iter()
-            .filter(|c| c.post_id == post_id)
-            .cloned()
-            .collect()
+        let ids = state.comments_by_post.get(&post_id).map(Vec::as_slice).unwrap_or_default();
+        let ids: Vec<u64> = ids.iter().copied().filter(|id| state.is_listed_comment(id)).collect();
+        state.comments_by_ids(&ids)
+    }
+
+    // Query: Get a page of comments for a post
+    #[export]
+    pub fn get_post_comments(&self, post_id: u64, cursor: Option<u64>, limit: u32, order: SortOrder, viewer: Option<ActorId>) -> CommentPage {
+        let state = state_ref();
+        let ids = state.comments_by_post.get(&post_id).map(Vec::as_slice).unwrap_or_default();
+        let ids = index_ids(ids, cursor, order).filter(|id| state.is_visible_comment(id, viewer));
+        let (ids, next_cursor) = paginate(ids, limit);
+        CommentPage { items: state.comments_by_ids(&ids), next_cursor }
     }
 
-    // Query: Get all comments
+    // Query: Get direct replies to a comment
+    #[export]
+    pub fn get_replies(&self, parent_id: u64, cursor: Option<u64>, limit: u32, order: SortOrder, viewer: Option<ActorId>) -> CommentPage {
+        let state = state_ref();
+        let ids = state.replies_by_parent.get(&parent_id).map(Vec::as_slice).unwrap_or_default();
+        let ids = index_ids(ids, cursor, order).filter(|id| state.is_visible_comment(id, viewer));
+        let (ids, next_cursor) = paginate(ids, limit);
+        CommentPage { items: state.comments_by_ids(&ids), next_cursor }
+    }
+
+    // Query: Get all comments (unbounded, prefer get_comments)
     #[export]
     pub fn get_all_comments(&self) -> Vec<Comment> {
         let state = state_ref();
-        state.comments.iter().cloned().collect()
+        state.comments.values().filter(|c| !c.hidden).cloned().collect()
+    }
+
+    // Query: Get a page of comments across all posts
+    #[export]
+    pub fn get_comments(&self, cursor: Option<u64>, limit: u32, order: SortOrder) -> CommentPage {
+        let state = state_ref();
+        let ids = map_ids(&state.comments, cursor, order).filter(|id| state.is_listed_comment(id));
+        let (ids, next_cursor) = paginate(ids, limit);
+        CommentPage { items: state.comments_by_ids(&ids), next_cursor }
+    }
+
+    // Query: Whether a wallet currently upvotes a post
+    #[export]
+    pub fn has_upvoted(&self, post_id: u64, wallet: ActorId) -> bool {
+        let state = state_ref();
+        state.current_vote(Target::Post(post_id), &wallet) == VoteDirection::Up
+    }
+
+    // Query: Whether a wallet currently upvotes a comment
+    #[export]
+    pub fn has_upvoted_comment(&self, comment_id: u64, wallet: ActorId) -> bool {
+        let state = state_ref();
+        state.current_vote(Target::Comment(comment_id), &wallet) == VoteDirection::Up
+    }
+
+    // Query: Get a wallet's votes on a page of posts and comments, at most MAX_PAGE_SIZE targets
+    #[export]
+    pub fn get_vote_states(&self, wallet: ActorId, targets: Vec<Target>) -> Vec<VoteState> {
+        let state = state_ref();
+        targets
+            .into_iter()
+            .take(MAX_PAGE_SIZE as usize)
+            .map(|target| VoteState { target, direction: state.current_vote(target, &wallet) })
+            .collect()
+    }
+
+    // Query: Get previous versions of a post, oldest first
+    #[export]
+    pub fn get_post_revisions(&self, post_id: u64) -> Vec<Revision> {
+        let state = state_ref();
+        state.post_revisions.get(&post_id).cloned().unwrap_or_default()
+    }
+
+    // Query: Get previous versions of a comment, oldest first
+    #[export]
+    pub fn get_comment_revisions(&self, comment_id: u64) -> Vec<Revision> {
+        let state = state_ref();
+        state.comment_revisions.get(&comment_id).cloned().unwrap_or_default()
+    }
+
+    // Query: Get a community by id
+    #[export]
+    pub fn get_community(&self, community_id: u64) -> Option<Community> {
+        let state = state_ref();
+        state.communities.get(&community_id).cloned()
+    }
+
+    // Query: Get a community by its slug
+    #[export]
+    pub fn get_community_by_slug(&self, slug: String) -> Option<Community> {
+        let state = state_ref();
+        let id = state.community_by_slug.get(&slug.to_lowercase())?;
+        state.communities.get(id).cloned()
+    }
+
+    // Query: Get a page of communities
+    #[export]
+    pub fn list_communities(&self, cursor: Option<u64>, limit: u32, order: SortOrder) -> CommunityPage {
+        let state = state_ref();
+        let (ids, next_cursor) = paginate(map_ids(&state.communities, cursor, order), limit);
+        let items = ids.iter().filter_map(|id| state.communities.get(id)).cloned().collect();
+        CommunityPage { items, next_cursor }
+    }
+
+    // Query: Get a page of posts in a community
+    #[export]
+    pub fn get_posts_in_community(&self, community_id: u64, cursor: Option<u64>, limit: u32, order: SortOrder, viewer: Option<ActorId>) -> PostPage {
+        let state = state_ref();
+        let ids = state.posts_by_community.get(&community_id).map(Vec::as_slice).unwrap_or_default();
+        let ids = index_ids(ids, cursor, order).filter(|id| state.is_visible_post(id, viewer));
+        let (ids, next_cursor) = paginate(ids, limit);
+        PostPage { items: state.posts_by_ids(&ids), next_cursor }
+    }
+
+    // Query: Get a page of community members, ordered by account id
+    #[export]
+    pub fn get_community_members(&self, community_id: u64, cursor: Option<ActorId>, limit: u32) -> MemberPage {
+        let state = state_ref();
+        paginate_accounts(state.community_members.get(&community_id), cursor, limit)
+    }
+
+    // Query: Get a page of the accounts following someone
+    #[export]
+    pub fn get_followers(&self, account: ActorId, cursor: Option<ActorId>, limit: u32) -> MemberPage {
+        let state = state_ref();
+        paginate_accounts(state.followers.get(&account), cursor, limit)
+    }
+
+    // Query: Get a page of the accounts someone follows
+    #[export]
+    pub fn get_following(&self, account: ActorId, cursor: Option<ActorId>, limit: u32) -> MemberPage {
+        let state = state_ref();
+        paginate_accounts(state.following.get(&account), cursor, limit)
+    }
+
+    // Query: Get recent posts from accounts the viewer follows, newest first
+    #[export]
+    pub fn get_home_feed(&self, viewer: ActorId, cursor: Option<u64>, limit: u32) -> PostPage {
+        let state = state_ref();
+        let Some(following) = state.following.get(&viewer) else {
+            return PostPage { items: Vec::new(), next_cursor: None };
+        };
+
+        // No page can need more than limit + 1 posts from a single author
+        let per_author = limit.clamp(1, MAX_PAGE_SIZE) as usize + 1;
+        let mut ids: Vec<u64> = Vec::new();
+        for author in following {
+            let authored = state.posts_by_author.get(author).map(Vec::as_slice).unwrap_or_default();
+            ids.extend(
+                index_ids(authored, cursor, SortOrder::NewestFirst)
+                    .filter(|id| state.posts.get(id).is_some_and(|p| !p.hidden && !p.deleted))
+                    .take(per_author),
+            );
+        }
+        ids.sort_unstable_by(|a, b| b.cmp(a));
+
+        let (ids, next_cursor) = paginate(ids.into_iter(), limit);
+        PostPage { items: state.posts_by_ids(&ids), next_cursor }
     }
 
     // Query: Get user profile
@@ -373,6 +2337,34 @@ impl MiniRedditService {
         state.profiles.get(&wallet).cloned()
     }
 
+    // Query: Get a profile by username, ignoring case
+    #[export]
+    pub fn get_profile_by_username(&self, username: String) -> Option<Profile> {
+        let state = state_ref();
+        let wallet = state.usernames.get(&username.to_ascii_lowercase())?;
+        state.profiles.get(wallet).cloned()
+    }
+
+    // Query: Get the $VIBES paid per action
+    #[export]
+    pub fn get_reward_table(&self) -> RewardTable {
+        let state = state_ref();
+        state.config.rewards.clone()
+    }
+
+    // Query: Get how much more an actor can earn today and what each action would pay
+    #[export]
+    pub fn get_reward_allowance(&self, wallet: ActorId) -> RewardAllowance {
+        state_ref().reward_allowance(&wallet, exec::block_timestamp() / MS_PER_DAY)
+    }
+
+    // Query: Get the current rate limits
+    #[export]
+    pub fn get_rate_limits(&self) -> RateLimits {
+        let state = state_ref();
+        state.config.rate_limits.clone()
+    }
+
     // Query: Get vibes balance
     #[export]
     pub fn get_vibes_balance(&self, wallet: ActorId) -> u64 {
@@ -386,9 +2378,14 @@ pub struct LumioSocialProgram(());
 #[sails_rs::program]
 impl LumioSocialProgram {
     // Program constructor
-    pub fn new(config: Config) -> Self {
+    pub fn new(config: Config, admin: ActorId) -> Self {
+        // A zero admin could never be replaced, leaving config and pauses locked forever
+        assert!(!admin.is_zero(), "Zero address");
         unsafe {
-            STATE = Some(ForumState::default());
+            STATE = Some(ForumState {
+                admin,
+                ..Default::default()
+            });
         }
         SessionService::init(config);
         Self(())
@@ -399,6 +2396,21 @@ impl LumioSocialProgram {
         MiniRedditService::new()
     }
 
+    // Expose $VIBES as a fungible token
+    pub fn vibes(&self) -> VibesService {
+        VibesService::new()
+    }
+
+    // Expose admin role and configuration
+    pub fn admin(&self) -> AdminService {
+        AdminService::new()
+    }
+
+    // Expose reporting and moderation
+    pub fn moderation(&self) -> ModerationService {
+        ModerationService::new()
+    }
+
     // Expose session service for signless transactions
     #[export(route = "Session")]
     pub fn session(&self) -> SessionService {
@@ -410,6 +2422,166 @@ impl LumioSocialProgram {
 #[no_mangle]
 extern "C" fn state() {
     let state = state_ref();
-    let posts: Vec<Post> = state.posts.iter().rev().cloned().collect();
+    let posts: Vec<Post> = state.posts.values().rev().filter(|p| !p.hidden).cloned().collect();
     msg::reply(posts, 0).expect("Failed to share state");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    const DAY: u64 = 20_000;
+
+    fn wallet(n: u8) -> ActorId {
+        ActorId::from([n; 32])
+    }
+
+    fn post(id: u64, author: ActorId) -> Post {
+        Post {
+            id,
+            author,
+            community_id: None,
+            text: String::new(),
+            image_uri: None,
+            created_at: 0,
+            edited_at: None,
+            deleted: false,
+            hidden: false,
+            locked: false,
+            pinned: false,
+            upvotes: 0,
+            downvotes: 0,
+            score: 0,
+            comment_count: 0,
+            tips_received: 0,
+        }
+    }
+
+    #[test]
+    fn rewards_halve_after_full_rate_then_stop() {
+        let mut state = ForumState::default();
+        let paid: Vec<u64> = (0..13).map(|_| state.take_reward(wallet(1), RewardAction::Post, DAY)).collect();
+        assert_eq!(paid, [50, 50, 50, 50, 50, 25, 12, 6, 3, 1, 0, 0, 0]);
+        assert_eq!(state.reward_window(&wallet(1), DAY).earned, 297);
+
+        // Shifting past the width of u64 pays nothing instead of overflowing
+        let window = RewardWindow { day: DAY, earned: 0, counts: [u32::MAX, 0, 0, 0] };
+        assert_eq!(state.config.rewards.next_amount(RewardAction::Post, &window), 0);
+    }
+
+    #[test]
+    fn daily_cap_clamps_every_action() {
+        let mut state = ForumState::default();
+        state.config.rewards.daily_cap = 120;
+        let paid: Vec<u64> = (0..4).map(|_| state.take_reward(wallet(1), RewardAction::Post, DAY)).collect();
+        assert_eq!(paid, [50, 50, 20, 0]);
+        assert_eq!(state.take_reward(wallet(1), RewardAction::Comment, DAY), 0);
+        assert_eq!(state.reward_window(&wallet(1), DAY).earned, 120);
+        // Other wallets have their own cap
+        assert_eq!(state.take_reward(wallet(2), RewardAction::Post, DAY), 50);
+    }
+
+    #[test]
+    fn allowance_matches_next_payout() {
+        let mut state = ForumState::default();
+        state.config.rewards.daily_cap = 400;
+        let actions = [RewardAction::Post, RewardAction::Comment, RewardAction::UpvoteGiven, RewardAction::UpvoteReceived];
+        for i in 0..40 {
+            let action = actions[i % actions.len()];
+            let allowance = state.reward_allowance(&wallet(1), DAY);
+            let expected = match action {
+                RewardAction::Post => allowance.next_post,
+                RewardAction::Comment => allowance.next_comment,
+                RewardAction::UpvoteGiven => allowance.next_upvote_given,
+                RewardAction::UpvoteReceived => allowance.next_upvote_received,
+            };
+            assert_eq!(state.take_reward(wallet(1), action, DAY), expected, "action {}", i);
+
+            let allowance = state.reward_allowance(&wallet(1), DAY);
+            assert_eq!(allowance.earned_today + allowance.remaining_today, 400);
+        }
+        assert_eq!(state.reward_allowance(&wallet(1), DAY).remaining_today, 0);
+    }
+
+    #[test]
+    fn rewards_reset_on_the_next_day() {
+        let mut state = ForumState::default();
+        state.config.rewards.daily_cap = 100;
+        while state.take_reward(wallet(1), RewardAction::Post, DAY) > 0 {}
+
+        // The stored window is stale, the allowance already shows the new day
+        let allowance = state.reward_allowance(&wallet(1), DAY + 1);
+        assert_eq!((allowance.earned_today, allowance.next_post), (0, 50));
+        assert_eq!(allowance.resets_at, (DAY + 2) * MS_PER_DAY);
+
+        assert_eq!(state.take_reward(wallet(1), RewardAction::Post, DAY + 1), 50);
+        assert_eq!(state.reward_window(&wallet(1), DAY + 1).earned, 50);
+    }
+
+    #[test]
+    fn each_voter_pays_out_once_per_target() {
+        let mut state = ForumState::default();
+        let (author, voter) = (wallet(1), wallet(2));
+        state.posts.insert(1, post(1, author));
+
+        assert_eq!(state.claim_vote_reward(Target::Post(1), voter), Some(author));
+        assert_eq!(state.claim_vote_reward(Target::Post(1), voter), None);
+        assert_eq!(state.claim_vote_reward(Target::Post(1), author), None);
+        assert_eq!(state.claim_vote_reward(Target::Post(2), voter), None);
+        assert_eq!(state.claim_vote_reward(Target::Post(1), wallet(3)), Some(author));
+    }
+
+    #[test]
+    fn pages_follow_cursors_in_both_orders() {
+        let map: BTreeMap<u64, ()> = (1..=7).map(|id| (id, ())).collect();
+        let index: Vec<u64> = (1..=7).collect();
+
+        for (order, pages) in [
+            (SortOrder::NewestFirst, [(vec![7, 6, 5], Some(4)), (vec![4, 3, 2], Some(1)), (vec![1], None)]),
+            (SortOrder::OldestFirst, [(vec![1, 2, 3], Some(4)), (vec![4, 5, 6], Some(7)), (vec![7], None)]),
+        ] {
+            let mut cursor = None;
+            for expected in pages {
+                let page = paginate(map_ids(&map, cursor, order), 3);
+                assert_eq!(page, paginate(index_ids(&index, cursor, order), 3));
+                assert_eq!(page, expected);
+                cursor = page.1;
+            }
+        }
+    }
+
+    #[test]
+    fn cursor_missing_from_the_index_starts_at_its_neighbour() {
+        let map: BTreeMap<u64, ()> = [2, 4, 6, 8].into_iter().map(|id| (id, ())).collect();
+        let index = [2, 4, 6, 8];
+
+        for (cursor, order, expected) in [
+            (5, SortOrder::NewestFirst, vec![4, 2]),
+            (5, SortOrder::OldestFirst, vec![6, 8]),
+            (100, SortOrder::NewestFirst, vec![8, 6, 4, 2]),
+            (100, SortOrder::OldestFirst, vec![]),
+            (0, SortOrder::NewestFirst, vec![]),
+            (0, SortOrder::OldestFirst, vec![2, 4, 6, 8]),
+        ] {
+            assert_eq!(paginate(map_ids(&map, Some(cursor), order), 10), (expected.clone(), None));
+            assert_eq!(paginate(index_ids(&index, Some(cursor), order), 10), (expected, None));
+        }
+    }
+
+    #[test]
+    fn limit_is_clamped_to_page_size() {
+        let index: Vec<u64> = (0..120).collect();
+
+        let (items, next_cursor) = paginate(index_ids(&index, None, SortOrder::OldestFirst), 0);
+        assert_eq!((items, next_cursor), (vec![0], Some(1)));
+
+        let (items, next_cursor) = paginate(index_ids(&index, None, SortOrder::OldestFirst), u32::MAX);
+        assert_eq!(items.len(), MAX_PAGE_SIZE as usize);
+        assert_eq!(next_cursor, Some(u64::from(MAX_PAGE_SIZE)));
+
+        let (items, next_cursor) = paginate(index_ids(&index, Some(119), SortOrder::NewestFirst), 1000);
+        assert_eq!(items.first(), Some(&119));
+        assert_eq!(next_cursor, Some(119 - u64::from(MAX_PAGE_SIZE)));
+    }
+}
@@ -0,0 +1,418 @@
+// Content reports and the moderator queue that resolves them
+
+use sails_rs::{
+    gstd::{exec, msg},
+    prelude::*,
+};
+
+use crate::{
+    get_actor, paginate, state_mut, state_ref, ActionsForSession, Ban, BanKind, ForumState, ModAction, ModLogEntry, ModLogPage,
+    PausableAction, ReasonCount, Report, ReportReason, ReportResolution, SortOrder, Storage, Target,
+    MAX_MOD_REASON_LEN, MAX_PAGE_SIZE, MAX_PINNED_POSTS, REMOVED_TOMBSTONE,
+};
+
+#[derive(Encode, Decode, TypeInfo)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub enum ModerationEvent {
+    Reported { target: Target, reporter: ActorId, reason: ReportReason, count: u32 },
+    ReportResolved { target: Target, moderator: ActorId, resolution: ReportResolution },
+    HiddenChanged { target: Target, moderator: ActorId, hidden: bool, reason: String },
+    PostLockChanged { post_id: u64, moderator: ActorId, locked: bool, reason: String },
+    PostPinChanged { post_id: u64, moderator: ActorId, pinned: bool },
+    CommunityModeratorAdded { community_id: u64, account: ActorId, by: ActorId },
+    CommunityModeratorRemoved { community_id: u64, account: ActorId, by: ActorId },
+    Banned { ban: Ban },
+    Unbanned { account: ActorId, community_id: Option<u64>, moderator: ActorId },
+    ModLogged { entry: ModLogEntry },
+}
+
+#[derive(Clone)]
+pub struct ModerationService(());
+
+impl ModerationService {
+    pub fn new() -> Self {
+        Self(())
+    }
+
+    fn report(&mut self, target: Target, reason: ReportReason, session_for_account: Option<ActorId>) -> Result<u32, String> {
+        let state = state_mut();
+        state.ensure_active(PausableAction::Report)?;
+        let msg_src = msg::source();
+        let sessions = Storage::get_session_map();
+        let reporter = get_actor(&sessions, &msg_src, &session_for_account, ActionsForSession::Report);
+
+        let (author, deleted) = match target {
+            Target::Post(id) => state.posts.get(&id).map(|p| (p.author, p.deleted)).ok_or("Post not found")?,
+            Target::Comment(id) => state.comments.get(&id).map(|c| (c.author, c.deleted)).ok_or("Comment not found")?,
+        };
+        if deleted {
+            return Err("Content was deleted".to_string());
+        }
+        if author == reporter {
+            return Err("Cannot report your own content".to_string());
+        }
+        if !state.report_reporters.entry(target).or_default().insert(reporter) {
+            return Err("Already reported".to_string());
+        }
+
+        let now = exec::block_timestamp();
+        let report = state.reports.entry(target).or_insert_with(|| Report {
+            target,
+            count: 0,
+            reasons: Vec::new(),
+            first_reported_at: now,
+            last_reported_at: now,
+        });
+        report.count += 1;
+        report.last_reported_at = now;
+        match report.reasons.iter_mut().find(|r| r.reason == reason) {
+            Some(r) => r.count += 1,
+            None => report.reasons.push(ReasonCount { reason, count: 1 }),
+        }
+        let count = report.count;
+
+        self.emit_event(ModerationEvent::Reported { target, reporter, reason, count })
+            .expect("Notification failure");
+
+        Ok(count)
+    }
+
+    fn log(&mut self, moderator: ActorId, action: ModAction, reason: String) {
+        let entry = state_mut().log_mod_action(moderator, action, reason);
+        self.emit_event(ModerationEvent::ModLogged { entry })
+            .expect("Notification failure");
+    }
+}
+
+fn validate_reason(reason: &str) -> Result<(), String> {
+    if reason.len() > MAX_MOD_REASON_LEN {
+        return Err(format!("Reason too long (max {} chars)", MAX_MOD_REASON_LEN));
+    }
+    Ok(())
+}
+
+// Global moderators ban program-wide, community moderators within their community
+fn ensure_can_ban(state: &ForumState, moderator: &ActorId, community_id: Option<u64>) -> Result<(), String> {
+    let allowed = match community_id {
+        Some(cid) => {
+            if !state.communities.contains_key(&cid) {
+                return Err("Community not found".to_string());
+            }
+            state.is_community_moderator(cid, moderator)
+        }
+        None => state.is_moderator(moderator),
+    };
+    if !allowed {
+        return Err("Only moderators can ban accounts".to_string());
+    }
+    Ok(())
+}
+
+fn set_hidden(state: &mut ForumState, target: Target, hidden: bool) -> Result<(), String> {
+    let flag = match target {
+        Target::Post(id) => &mut state.posts.get_mut(&id).ok_or("Post not found")?.hidden,
+        Target::Comment(id) => &mut state.comments.get_mut(&id).ok_or("Comment not found")?.hidden,
+    };
+    if *flag == hidden {
+        return Err(if hidden { "Already hidden" } else { "Not hidden" }.to_string());
+    }
+    *flag = hidden;
+    // A hidden pin would keep its slot without showing in the feed
+    if let (Target::Post(id), true) = (target, hidden) {
+        state.unpin_post(id);
+    }
+    Ok(())
+}
+
+fn apply_resolution(state: &mut ForumState, target: Target, resolution: ReportResolution) -> ModAction {
+    match (resolution, target) {
+        (ReportResolution::Dismiss, _) => return ModAction::DismissReports { target },
+        (ReportResolution::Hide, Target::Post(id)) => {
+            if let Some(post) = state.posts.get_mut(&id) {
+                post.hidden = true;
+            }
+            state.unpin_post(id);
+        }
+        (ReportResolution::Hide, Target::Comment(id)) => {
+            if let Some(comment) = state.comments.get_mut(&id) {
+                comment.hidden = true;
+            }
+        }
+        (ReportResolution::Remove, Target::Post(id)) => state.tombstone_post(id, REMOVED_TOMBSTONE),
+        (ReportResolution::Remove, Target::Comment(id)) => state.tombstone_comment(id, REMOVED_TOMBSTONE),
+    }
+    match resolution {
+        ReportResolution::Hide => ModAction::Hide { target, hidden: true },
+        _ => ModAction::Remove { target },
+    }
+}
+
+#[sails_rs::service(events = ModerationEvent)]
+impl ModerationService {
+    // Report a post, each account counts once per target; returns the open report count
+    #[export]
+    pub fn report_post(&mut self, post_id: u64, reason: ReportReason, session_for_account: Option<ActorId>) -> Result<u32, String> {
+        self.report(Target::Post(post_id), reason, session_for_account)
+    }
+
+    // Report a comment, each account counts once per target; returns the open report count
+    #[export]
+    pub fn report_comment(&mut self, comment_id: u64, reason: ReportReason, session_for_account: Option<ActorId>) -> Result<u32, String> {
+        self.report(Target::Comment(comment_id), reason, session_for_account)
+    }
+
+    // Close the open reports on a target by dismissing, hiding or removing it
+    #[export]
+    pub fn resolve_report(&mut self, target: Target, resolution: ReportResolution, reason: String) -> Result<(), String> {
+        validate_reason(&reason)?;
+        let state = state_mut();
+        let moderator = msg::source();
+        if !state.can_moderate(target, &moderator) {
+            return Err("Only moderators can resolve reports".to_string());
+        }
+        if !state.close_reports(target) {
+            return Err("No open report for this target".to_string());
+        }
+        let action = apply_resolution(state, target, resolution);
+
+        self.emit_event(ModerationEvent::ReportResolved { target, moderator, resolution })
+            .expect("Notification failure");
+        self.log(moderator, action, reason);
+
+        Ok(())
+    }
+
+    // Hide or unhide a post or comment; hidden content is left out of feeds
+    #[export]
+    pub fn set_hidden(&mut self, target: Target, hidden: bool, reason: String) -> Result<(), String> {
+        validate_reason(&reason)?;
+        let state = state_mut();
+        let moderator = msg::source();
+        if !state.can_moderate(target, &moderator) {
+            return Err("Only moderators can hide content".to_string());
+        }
+        set_hidden(state, target, hidden)?;
+
+        self.emit_event(ModerationEvent::HiddenChanged { target, moderator, hidden, reason: reason.clone() })
+            .expect("Notification failure");
+        self.log(moderator, ModAction::Hide { target, hidden }, reason);
+
+        Ok(())
+    }
+
+    // Lock or unlock a post, locked posts reject new comments
+    #[export]
+    pub fn set_locked(&mut self, post_id: u64, locked: bool, reason: String) -> Result<(), String> {
+        validate_reason(&reason)?;
+        let state = state_mut();
+        let moderator = msg::source();
+        if !state.can_moderate(Target::Post(post_id), &moderator) {
+            return Err("Only moderators can lock posts".to_string());
+        }
+        let post = state.posts.get_mut(&post_id).ok_or("Post not found")?;
+        if post.locked == locked {
+            return Err(if locked { "Already locked" } else { "Not locked" }.to_string());
+        }
+        post.locked = locked;
+
+        self.emit_event(ModerationEvent::PostLockChanged { post_id, moderator, locked, reason: reason.clone() })
+            .expect("Notification failure");
+        self.log(moderator, ModAction::Lock { post_id, locked }, reason);
+
+        Ok(())
+    }
+
+    // Pin or unpin a post at the top of its community feed (or the global feed)
+    #[export]
+    pub fn set_pinned(&mut self, post_id: u64, pinned: bool, reason: String) -> Result<(), String> {
+        validate_reason(&reason)?;
+        let state = state_mut();
+        let moderator = msg::source();
+        if !state.can_moderate(Target::Post(post_id), &moderator) {
+            return Err("Only moderators can pin posts".to_string());
+        }
+        let post = state.posts.get_mut(&post_id).ok_or("Post not found")?;
+        if post.deleted {
+            return Err("Post was deleted".to_string());
+        }
+        if pinned && post.hidden {
+            return Err("Cannot pin a hidden post".to_string());
+        }
+        if post.pinned == pinned {
+            return Err(if pinned { "Already pinned" } else { "Not pinned" }.to_string());
+        }
+
+        let list = state.pinned_posts.entry(post.community_id).or_default();
+        if pinned {
+            if list.len() >= MAX_PINNED_POSTS {
+                return Err(format!("At most {} pinned posts", MAX_PINNED_POSTS));
+            }
+            list.push(post_id);
+        } else {
+            list.retain(|id| *id != post_id);
+        }
+        post.pinned = pinned;
+
+        self.emit_event(ModerationEvent::PostPinChanged { post_id, moderator, pinned })
+            .expect("Notification failure");
+        self.log(moderator, ModAction::Pin { post_id, pinned }, reason);
+
+        Ok(())
+    }
+
+    // Appoint a community moderator, allowed for the creator and global moderators
+    #[export]
+    pub fn add_community_moderator(&mut self, community_id: u64, account: ActorId) -> Result<(), String> {
+        let state = state_mut();
+        let by = msg::source();
+        let community = state.communities.get(&community_id).ok_or("Community not found")?;
+        if community.creator != by && !state.is_moderator(&by) {
+            return Err("Only the community creator can appoint moderators".to_string());
+        }
+        if !state.community_moderators.entry(community_id).or_default().insert(account) {
+            return Err("Already a moderator".to_string());
+        }
+
+        self.emit_event(ModerationEvent::CommunityModeratorAdded { community_id, account, by })
+            .expect("Notification failure");
+        let action = ModAction::ModeratorChanged { community_id: Some(community_id), account, added: true };
+        self.log(by, action, String::new());
+
+        Ok(())
+    }
+
+    #[export]
+    pub fn remove_community_moderator(&mut self, community_id: u64, account: ActorId) -> Result<(), String> {
+        let state = state_mut();
+        let by = msg::source();
+        let community = state.communities.get(&community_id).ok_or("Community not found")?;
+        if community.creator != by && !state.is_moderator(&by) {
+            return Err("Only the community creator can remove moderators".to_string());
+        }
+        if !state.community_moderators.get_mut(&community_id).is_some_and(|m| m.remove(&account)) {
+            return Err("Not a moderator".to_string());
+        }
+
+        self.emit_event(ModerationEvent::CommunityModeratorRemoved { community_id, account, by })
+            .expect("Notification failure");
+        let action = ModAction::ModeratorChanged { community_id: Some(community_id), account, added: false };
+        self.log(by, action, String::new());
+
+        Ok(())
+    }
+
+    // Ban or mute an account program-wide or in one community, until a timestamp or for good
+    #[export]
+    pub fn ban(&mut self, account: ActorId, community_id: Option<u64>, kind: BanKind, until: Option<u64>, reason: String) -> Result<(), String> {
+        validate_reason(&reason)?;
+        let state = state_mut();
+        let moderator = msg::source();
+        ensure_can_ban(state, &moderator, community_id)?;
+        let can_moderate = match community_id {
+            Some(cid) => state.is_community_moderator(cid, &account),
+            None => state.is_moderator(&account),
+        };
+        if can_moderate {
+            return Err("Cannot ban a moderator".to_string());
+        }
+        let now = exec::block_timestamp();
+        if until.is_some_and(|until| until <= now) {
+            return Err("Ban must end in the future".to_string());
+        }
+
+        let ban = Ban { account, community_id, kind, until, moderator, reason: reason.clone(), created_at: now };
+        state.bans.insert((account, community_id), ban.clone());
+
+        self.emit_event(ModerationEvent::Banned { ban })
+            .expect("Notification failure");
+        self.log(moderator, ModAction::Ban { account, community_id, kind, until }, reason);
+
+        Ok(())
+    }
+
+    // Lift a ban or mute before it runs out
+    #[export]
+    pub fn unban(&mut self, account: ActorId, community_id: Option<u64>, reason: String) -> Result<(), String> {
+        validate_reason(&reason)?;
+        let state = state_mut();
+        let moderator = msg::source();
+        ensure_can_ban(state, &moderator, community_id)?;
+        let now = exec::block_timestamp();
+        if !state.bans.remove(&(account, community_id)).is_some_and(|ban| ban.is_active(now)) {
+            return Err("Account is not banned".to_string());
+        }
+
+        self.emit_event(ModerationEvent::Unbanned { account, community_id, moderator })
+            .expect("Notification failure");
+        self.log(moderator, ModAction::Unban { account, community_id }, reason);
+
+        Ok(())
+    }
+
+    // Query: Get active bans and mutes in a community, or program-wide ones for None
+    #[export]
+    pub fn get_active_bans(&self, community_id: Option<u64>, offset: u32, limit: u32) -> Vec<Ban> {
+        let state = state_ref();
+        let now = exec::block_timestamp();
+        let mut bans: Vec<&Ban> = state
+            .bans
+            .values()
+            .filter(|ban| ban.community_id == community_id && ban.is_active(now))
+            .collect();
+        bans.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.account.cmp(&b.account)));
+
+        let limit = limit.clamp(1, MAX_PAGE_SIZE) as usize;
+        bans.into_iter().skip(offset as usize).take(limit).cloned().collect()
+    }
+
+    // Query: Get the appointed moderators of a community
+    #[export]
+    pub fn get_community_moderators(&self, community_id: u64) -> Vec<ActorId> {
+        let state = state_ref();
+        state
+            .community_moderators
+            .get(&community_id)
+            .map(|m| m.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    // Query: Get a page of the public moderation log, cursor is an entry id
+    #[export]
+    pub fn get_mod_log(&self, cursor: Option<u64>, limit: u32, order: SortOrder) -> ModLogPage {
+        let state = state_ref();
+        let len = state.mod_log.len() as u64;
+        let ids: Box<dyn Iterator<Item = u64>> = match order {
+            SortOrder::NewestFirst => Box::new((0..cursor.map_or(len, |c| c.saturating_add(1).min(len))).rev()),
+            SortOrder::OldestFirst => Box::new(cursor.unwrap_or(0).min(len)..len),
+        };
+        let (ids, next_cursor) = paginate(ids, limit);
+        let items = ids.iter().map(|id| state.mod_log[*id as usize].clone()).collect();
+        ModLogPage { items, next_cursor }
+    }
+
+    // Query: Get open reports, most reported first. None lists every report for global
+    // moderators, Some lists one community's reports for its moderators.
+    #[export]
+    pub fn get_open_reports(&self, community_id: Option<u64>, offset: u32, limit: u32) -> Result<Vec<Report>, String> {
+        let state = state_ref();
+        let moderator = msg::source();
+        let allowed = match community_id {
+            Some(cid) => state.is_community_moderator(cid, &moderator),
+            None => state.is_moderator(&moderator),
+        };
+        if !allowed {
+            return Err("Only moderators can view reports".to_string());
+        }
+
+        let mut reports: Vec<&Report> = state
+            .reports
+            .values()
+            .filter(|r| community_id.is_none() || state.community_of(r.target) == community_id)
+            .collect();
+        reports.sort_by(|a, b| b.count.cmp(&a.count).then(a.first_reported_at.cmp(&b.first_reported_at)));
+
+        let limit = limit.clamp(1, MAX_PAGE_SIZE) as usize;
+        Ok(reports.into_iter().skip(offset as usize).take(limit).cloned().collect())
+    }
+}
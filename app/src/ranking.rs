@@ -0,0 +1,150 @@
+// Feed ranking scores. Everything is integer fixed-point (x1000) since the
+// program runs without std and has no float log/pow.
+
+use crate::{FeedSort, Post};
+
+// Reddit's hot decay: 45000 seconds of age are worth one order of magnitude of votes
+const HOT_DECAY_MS: i64 = 45_000_000;
+const MS_PER_HOUR: u64 = 3_600_000;
+
+// log10(x) * 1000, using ilog2 plus a linear approximation of the fraction
+fn log10_milli(x: u64) -> i64 {
+    let x = x.max(1);
+    let k = x.ilog2();
+    let base = 1u64 << k;
+    let frac_milli = ((x - base) as u128 * 1000 / base as u128) as i64;
+    let log2_milli = i64::from(k) * 1000 + frac_milli;
+    log2_milli * 30_103 / 100_000
+}
+
+// Sign of the score times log10 of its magnitude, plus a time bonus that makes
+// newer posts outrank older ones with the same votes
+pub fn hot(post: &Post) -> i64 {
+    let order = log10_milli(post.score.unsigned_abs());
+    post.score.signum() * order + (post.created_at as i64) * 1000 / HOT_DECAY_MS
+}
+
+pub fn top(post: &Post) -> i64 {
+    post.score.saturating_mul(1000)
+}
+
+// Net score per hour of age, young posts gaining votes quickly rise first
+pub fn rising(post: &Post, now: u64) -> i64 {
+    let age_hours_milli = (now.saturating_sub(post.created_at) * 1000 / MS_PER_HOUR).max(1000);
+    post.score.saturating_mul(1_000_000) / age_hours_milli as i64
+}
+
+// Many votes split close to evenly between up and down. Zero if either side has none.
+pub fn controversial(post: &Post) -> i64 {
+    let (up, down) = (i64::from(post.upvotes), i64::from(post.downvotes));
+    if up == 0 || down == 0 {
+        return 0;
+    }
+    (up + down) * 1000 * up.min(down) / up.max(down)
+}
+
+pub fn rank(sort: FeedSort, post: &Post, now: u64) -> i64 {
+    match sort {
+        FeedSort::Hot => hot(post),
+        FeedSort::Top => top(post),
+        FeedSort::New => post.id as i64,
+        FeedSort::Rising => rising(post, now),
+        FeedSort::Controversial => controversial(post),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sails_rs::prelude::*;
+
+    const HOUR: u64 = MS_PER_HOUR;
+
+    fn post(id: u64, upvotes: u32, downvotes: u32, created_at: u64) -> Post {
+        Post {
+            id,
+            author: ActorId::zero(),
+            community_id: None,
+            text: String::new(),
+            image_uri: None,
+            created_at,
+            edited_at: None,
+            deleted: false,
+            hidden: false,
+            locked: false,
+            pinned: false,
+            upvotes,
+            downvotes,
+            score: i64::from(upvotes) - i64::from(downvotes),
+            comment_count: 0,
+            tips_received: 0,
+        }
+    }
+
+    #[test]
+    fn log10_is_close_and_monotonic() {
+        assert_eq!(log10_milli(0), 0);
+        assert_eq!(log10_milli(1), 0);
+        let mut power = 1u64;
+        for k in 0..=18 {
+            assert!((log10_milli(power) - k * 1000).abs() <= 50, "log10(10^{})", k);
+            power = power.saturating_mul(10);
+        }
+        let mut previous = 0;
+        for x in 1..5000 {
+            let current = log10_milli(x);
+            assert!(current >= previous, "log10 decreased at {}", x);
+            previous = current;
+        }
+    }
+
+    #[test]
+    fn hot_orders_by_score_then_age() {
+        let now = 1_000 * HOUR;
+        assert!(hot(&post(1, 100, 0, now)) > hot(&post(2, 10, 0, now)));
+        assert!(hot(&post(1, 10, 0, now)) > hot(&post(2, 10, 0, now - HOUR)));
+        // Ten times the votes make up for 12.5 hours of age
+        assert!(hot(&post(1, 100, 0, now - 12 * HOUR)) > hot(&post(2, 10, 0, now)));
+        assert!(hot(&post(1, 100, 0, now - 13 * HOUR)) < hot(&post(2, 10, 0, now)));
+    }
+
+    #[test]
+    fn negative_scores_rank_below_zero() {
+        let now = 1_000 * HOUR;
+        let (zero, negative, worse) = (post(1, 0, 0, now), post(2, 1, 11, now), post(3, 0, 100, now));
+        assert!(hot(&zero) > hot(&negative));
+        assert!(hot(&negative) > hot(&worse));
+        assert!(top(&zero) > top(&negative));
+        assert_eq!(top(&negative), -10_000);
+        assert!(rising(&negative, now) < 0);
+        assert!(rising(&zero, now) > rising(&negative, now));
+    }
+
+    #[test]
+    fn rising_favours_young_posts() {
+        let now = 100 * HOUR;
+        assert!(rising(&post(1, 10, 0, now - HOUR), now) > rising(&post(2, 10, 0, now - 10 * HOUR), now));
+        // Anything younger than an hour counts as one hour old
+        assert_eq!(rising(&post(1, 10, 0, now), now), rising(&post(2, 10, 0, now - HOUR / 2), now));
+        assert_eq!(rising(&post(1, 10, 0, now - HOUR), now), 10_000);
+    }
+
+    #[test]
+    fn controversial_needs_both_sides() {
+        assert_eq!(controversial(&post(1, 0, 0, 0)), 0);
+        assert_eq!(controversial(&post(1, 50, 0, 0)), 0);
+        assert_eq!(controversial(&post(1, 0, 50, 0)), 0);
+        // Even splits beat lopsided ones, and more votes beat fewer at the same split
+        assert!(controversial(&post(1, 50, 50, 0)) > controversial(&post(2, 90, 10, 0)));
+        assert!(controversial(&post(1, 100, 100, 0)) > controversial(&post(2, 10, 10, 0)));
+        assert_eq!(controversial(&post(1, 10, 40, 0)), controversial(&post(2, 40, 10, 0)));
+    }
+
+    #[test]
+    fn rank_dispatches_on_sort() {
+        let p = post(7, 3, 1, 0);
+        assert_eq!(rank(FeedSort::New, &p, 0), 7);
+        assert_eq!(rank(FeedSort::Top, &p, 0), top(&p));
+        assert_eq!(rank(FeedSort::Controversial, &p, 0), controversial(&p));
+    }
+}
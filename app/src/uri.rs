@@ -0,0 +1,209 @@
+// Image and avatar URIs: ipfs://<cid>[/path], https://<host>[/path] or a bare CID.
+// CIDs are decoded and checked for alphabet, length and multihash shape. They carry
+// no checksum, so a well-formed CID pointing at the wrong content still passes.
+
+use sails_rs::prelude::*;
+
+use crate::UriError;
+
+pub const MAX_URI_LEN: usize = 256;
+const MAX_HOST_LEN: usize = 253;
+const MAX_LABEL_LEN: usize = 63;
+
+const BASE58_ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+// CIDv0 is always a sha2-256 multihash: code 0x12, 32 byte digest
+const SHA2_256: u8 = 0x12;
+const SHA2_256_LEN: u8 = 32;
+const CID_V0_LEN: usize = 46;
+
+pub fn validate(uri: &str) -> Result<(), UriError> {
+    if uri.len() > MAX_URI_LEN {
+        return Err(UriError::TooLong { max: MAX_URI_LEN as u32 });
+    }
+    if let Some(rest) = uri.strip_prefix("ipfs://") {
+        let (cid, path) = rest.split_once('/').unwrap_or((rest, ""));
+        validate_cid(cid)?;
+        validate_path(path)
+    } else if let Some(rest) = uri.strip_prefix("https://") {
+        let (host, path) = rest.split_once('/').unwrap_or((rest, ""));
+        validate_host(host)?;
+        validate_path(path)
+    } else if uri.contains("://") {
+        Err(UriError::UnsupportedScheme)
+    } else {
+        validate_cid(uri)
+    }
+}
+
+// CIDv0 ("Qm...", base58btc) or CIDv1 in base32 ('b') or base58btc ('z') multibase
+fn validate_cid(cid: &str) -> Result<(), UriError> {
+    let valid = if cid.len() == CID_V0_LEN && cid.starts_with("Qm") {
+        decode_base58(cid).is_some_and(|b| b.len() == 34 && b[0] == SHA2_256 && b[1] == SHA2_256_LEN)
+    } else if let Some(rest) = cid.strip_prefix('b') {
+        decode_base32(rest).is_some_and(|b| parse_cid_v1(&b).is_some())
+    } else if let Some(rest) = cid.strip_prefix('z') {
+        decode_base58(rest).is_some_and(|b| parse_cid_v1(&b).is_some())
+    } else {
+        false
+    };
+    if !valid {
+        return Err(UriError::InvalidCid);
+    }
+    Ok(())
+}
+
+// <version = 1><codec><multihash code><digest length><digest>, all varints but the digest
+fn parse_cid_v1(bytes: &[u8]) -> Option<()> {
+    let (version, rest) = read_varint(bytes)?;
+    let (_codec, rest) = read_varint(rest)?;
+    let (_hash_fn, rest) = read_varint(rest)?;
+    let (digest_len, digest) = read_varint(rest)?;
+    (version == 1 && digest_len > 0 && digest.len() as u64 == digest_len).then_some(())
+}
+
+// Dot separated labels of letters, digits and '-', with an optional port
+fn validate_host(host: &str) -> Result<(), UriError> {
+    let (name, port) = host.split_once(':').unwrap_or((host, "443"));
+    let valid_port = port.bytes().all(|b| b.is_ascii_digit()) && port.parse::<u16>().is_ok_and(|p| p > 0);
+    let valid_labels = name.split('.').all(|label| {
+        !label.is_empty()
+            && label.len() <= MAX_LABEL_LEN
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-')
+    });
+    if name.len() > MAX_HOST_LEN || !name.contains('.') || !valid_labels || !valid_port {
+        return Err(UriError::InvalidHost);
+    }
+    Ok(())
+}
+
+fn validate_path(path: &str) -> Result<(), UriError> {
+    if !path.bytes().all(|b| b.is_ascii_graphic()) {
+        return Err(UriError::InvalidPath);
+    }
+    Ok(())
+}
+
+fn read_varint(bytes: &[u8]) -> Option<(u64, &[u8])> {
+    let mut value = 0u64;
+    for (i, b) in bytes.iter().enumerate().take(9) {
+        value |= u64::from(b & 0x7f) << (7 * i);
+        if b & 0x80 == 0 {
+            return Some((value, &bytes[i + 1..]));
+        }
+    }
+    None
+}
+
+fn decode_base58(s: &str) -> Option<Vec<u8>> {
+    // Little-endian big number, reversed at the end
+    let mut bytes: Vec<u8> = Vec::new();
+    for c in s.bytes() {
+        let mut carry = BASE58_ALPHABET.iter().position(|&a| a == c)? as u32;
+        for b in bytes.iter_mut() {
+            carry += u32::from(*b) * 58;
+            *b = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+    // Each leading '1' stands for a leading zero byte
+    let zeros = s.bytes().take_while(|&c| c == b'1').count();
+    bytes.resize(bytes.len() + zeros, 0);
+    bytes.reverse();
+    Some(bytes)
+}
+
+// RFC 4648 lowercase base32 without padding
+fn decode_base32(s: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(s.len() * 5 / 8);
+    let (mut buffer, mut bits) = (0u32, 0u32);
+    for c in s.bytes() {
+        let value = match c {
+            b'a'..=b'z' => c - b'a',
+            b'2'..=b'7' => c - b'2' + 26,
+            _ => return None,
+        };
+        buffer = (buffer << 5) | u32::from(value);
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    // Leftover bits are padding and must be zero
+    (bits < 5 && buffer == 0).then_some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CID_V0: &str = "QmYwAPJzv5CZsnA625s3Xf2nemtYgPpHdWEz79ojWnPbdG";
+    const CID_V1_BASE32: &str = "bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi";
+    const CID_V1_BASE58: &str = "zdj7WWeQ43G6JJvLWQWZpyHuAMq6uYWRjkBXFad11vE2LHhQ7";
+
+    #[test]
+    fn accepts_valid_cids() {
+        assert_eq!(validate(CID_V0), Ok(()));
+        assert_eq!(validate(CID_V1_BASE32), Ok(()));
+        assert_eq!(validate(CID_V1_BASE58), Ok(()));
+    }
+
+    #[test]
+    fn rejects_truncated_cids() {
+        assert_eq!(validate(&CID_V0[..CID_V0.len() - 1]), Err(UriError::InvalidCid));
+        assert_eq!(validate(&CID_V1_BASE32[..CID_V1_BASE32.len() - 2]), Err(UriError::InvalidCid));
+        assert_eq!(validate(&CID_V1_BASE58[..CID_V1_BASE58.len() - 1]), Err(UriError::InvalidCid));
+        assert_eq!(validate("b"), Err(UriError::InvalidCid));
+        assert_eq!(validate(""), Err(UriError::InvalidCid));
+    }
+
+    #[test]
+    fn rejects_bad_alphabet() {
+        // '0' and 'l' are not base58, '1' and uppercase are not lowercase base32
+        assert_eq!(validate("QmYwAPJzv5CZsnA625s3Xf2nemtYgPpHdWEz79ojWnPbd0"), Err(UriError::InvalidCid));
+        assert_eq!(validate("QmYwAPJzv5CZsnA625s3Xf2nemtYgPpHdWEz79ojWnPbdl"), Err(UriError::InvalidCid));
+        assert_eq!(validate("bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzd1"), Err(UriError::InvalidCid));
+        assert_eq!(validate("bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdI"), Err(UriError::InvalidCid));
+    }
+
+    #[test]
+    fn rejects_non_zero_base32_padding() {
+        // Same length as the valid CID, only the two trailing padding bits differ
+        assert_eq!(validate("bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdj"), Err(UriError::InvalidCid));
+    }
+
+    #[test]
+    fn checks_schemes() {
+        assert_eq!(validate(&format!("ipfs://{}", CID_V0)), Ok(()));
+        assert_eq!(validate(&format!("ipfs://{}/img/a.png", CID_V1_BASE32)), Ok(()));
+        assert_eq!(validate("ipfs://not-a-cid"), Err(UriError::InvalidCid));
+        assert_eq!(validate("https://gateway.pinata.cloud/ipfs/Qm"), Ok(()));
+        assert_eq!(validate("http://example.com/a.png"), Err(UriError::UnsupportedScheme));
+        assert_eq!(validate("ftp://example.com/a.png"), Err(UriError::UnsupportedScheme));
+        assert_eq!(validate("https://example.com/a b.png"), Err(UriError::InvalidPath));
+        assert_eq!(validate(&format!("https://example.com/{}", "a".repeat(MAX_URI_LEN))), Err(UriError::TooLong { max: MAX_URI_LEN as u32 }));
+    }
+
+    #[test]
+    fn checks_hosts() {
+        assert_eq!(validate("https://example.com"), Ok(()));
+        assert_eq!(validate("https://sub-domain.example.com:8443/x"), Ok(()));
+        assert_eq!(validate("https://example.com:65535/x"), Ok(()));
+        assert_eq!(validate("https://example.com:99999/x"), Err(UriError::InvalidHost));
+        assert_eq!(validate("https://example.com:0/x"), Err(UriError::InvalidHost));
+        assert_eq!(validate("https://example.com:/x"), Err(UriError::InvalidHost));
+        assert_eq!(validate("https://example.com:+443/x"), Err(UriError::InvalidHost));
+        assert_eq!(validate("https://localhost/x"), Err(UriError::InvalidHost));
+        assert_eq!(validate("https://-bad.example.com"), Err(UriError::InvalidHost));
+        assert_eq!(validate("https://exa_mple.com"), Err(UriError::InvalidHost));
+        assert_eq!(validate("https://example..com"), Err(UriError::InvalidHost));
+        assert_eq!(validate("https:///a.png"), Err(UriError::InvalidHost));
+    }
+}
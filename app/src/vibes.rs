@@ -0,0 +1,165 @@
+// $VIBES as a fungible token. Balances are the same ones MiniReddit rewards are
+// credited to; this service exposes them through the standard VFT interface.
+
+use sails_rs::{gstd::msg, prelude::*, U256};
+
+use crate::{state_mut, state_ref, PausableAction};
+
+const NAME: &str = "Vibes";
+const SYMBOL: &str = "VIBES";
+const DECIMALS: u8 = 0;
+// Route the program exposes this service under, see `Program::vibes`
+const ROUTE: &str = "Vibes";
+
+#[derive(Encode, Decode, TypeInfo)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub enum VibesEvent {
+    Approval { owner: ActorId, spender: ActorId, value: U256 },
+    Transfer { from: ActorId, to: ActorId, value: U256 },
+}
+
+#[derive(Clone)]
+pub struct VibesService(());
+
+impl VibesService {
+    pub fn new() -> Self {
+        Self(())
+    }
+}
+
+// Balances are stored as u64, larger amounts can never be covered
+fn to_amount(value: U256) -> u64 {
+    if value > U256::from(u64::MAX) {
+        panic!("Amount exceeds u64");
+    }
+    value.low_u64()
+}
+
+fn ensure_active() {
+    if let Err(e) = state_ref().ensure_active(PausableAction::Transfer) {
+        panic!("{}", e);
+    }
+}
+
+fn transfer_balance(from: ActorId, to: ActorId, amount: u64) {
+    if let Err(e) = state_mut().move_vibes(from, to, amount) {
+        panic!("{}", e);
+    }
+}
+
+// MiniReddit mints rewards and moves tips outside this service. Emit those on the
+// Vibes route too, so VFT indexers see every balance change; mints come from zero.
+pub(crate) fn notify_transfer(from: ActorId, to: ActorId, amount: u64) {
+    let event = VibesEvent::Transfer { from, to, value: amount.into() };
+    sails_rs::gstd::__emit_event_with_route(&ROUTE.encode(), event).expect("Notification failure");
+}
+
+#[sails_rs::service(events = VibesEvent)]
+impl VibesService {
+    // Transfer vibes from the caller to another account
+    #[export]
+    pub fn transfer(&mut self, to: ActorId, value: U256) -> bool {
+        ensure_active();
+        let from = msg::source();
+        let amount = to_amount(value);
+        assert!(!to.is_zero(), "Zero address");
+
+        if amount == 0 || from == to {
+            return false;
+        }
+        transfer_balance(from, to, amount);
+
+        self.emit_event(VibesEvent::Transfer { from, to, value })
+            .expect("Notification failure");
+        true
+    }
+
+    // Allow a spender to transfer up to `value` of the caller's vibes
+    #[export]
+    pub fn approve(&mut self, spender: ActorId, value: U256) -> bool {
+        ensure_active();
+        let owner = msg::source();
+        let amount = to_amount(value);
+        assert!(!spender.is_zero(), "Zero address");
+
+        if owner == spender {
+            return false;
+        }
+        let state = state_mut();
+        let key = (owner, spender);
+        let changed = if amount == 0 {
+            state.vibes_allowances.remove(&key).is_some()
+        } else {
+            state.vibes_allowances.insert(key, amount) != Some(amount)
+        };
+        if !changed {
+            return false;
+        }
+
+        self.emit_event(VibesEvent::Approval { owner, spender, value })
+            .expect("Notification failure");
+        true
+    }
+
+    // Spend part of an allowance granted to the caller
+    #[export]
+    pub fn transfer_from(&mut self, from: ActorId, to: ActorId, value: U256) -> bool {
+        ensure_active();
+        let spender = msg::source();
+        let amount = to_amount(value);
+        assert!(!to.is_zero(), "Zero address");
+
+        if amount == 0 || from == to {
+            return false;
+        }
+        if spender != from {
+            let state = state_mut();
+            let key = (from, spender);
+            let allowance = state.vibes_allowances.get(&key).copied().unwrap_or(0);
+            let remaining = allowance.checked_sub(amount).expect("Insufficient allowance");
+            if remaining == 0 {
+                state.vibes_allowances.remove(&key);
+            } else {
+                state.vibes_allowances.insert(key, remaining);
+            }
+        }
+        transfer_balance(from, to, amount);
+
+        self.emit_event(VibesEvent::Transfer { from, to, value })
+            .expect("Notification failure");
+        true
+    }
+
+    #[export]
+    pub fn allowance(&self, owner: ActorId, spender: ActorId) -> U256 {
+        let state = state_ref();
+        state.vibes_allowances.get(&(owner, spender)).copied().unwrap_or(0).into()
+    }
+
+    #[export]
+    pub fn balance_of(&self, account: ActorId) -> U256 {
+        let state = state_ref();
+        state.vibes_balances.get(&account).copied().unwrap_or(0).into()
+    }
+
+    #[export]
+    pub fn total_supply(&self) -> U256 {
+        state_ref().vibes_total_supply.into()
+    }
+
+    #[export]
+    pub fn name(&self) -> String {
+        NAME.to_string()
+    }
+
+    #[export]
+    pub fn symbol(&self) -> String {
+        SYMBOL.to_string()
+    }
+
+    #[export]
+    pub fn decimals(&self) -> u8 {
+        DECIMALS
+    }
+}